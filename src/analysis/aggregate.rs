@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::telegram::RawComment;
+use super::pipeline::author_key;
+
+/// A group is classified as soon as it has this many comments
+const MAX_COMMENTS: usize = 5;
+
+/// Holds back each author's comments in a channel until they go quiet for the
+/// idle window, so a person is classified once on everything they wrote
+/// rather than comment by comment. Groups come out in arrival order; see `merge`.
+pub struct AuthorAggregator {
+    idle: Duration,
+    /// (channel, author key) → comments in arrival order
+    groups: HashMap<(String, String), Group>,
+}
+
+struct Group {
+    comments: Vec<RawComment>,
+    last_at: Instant,
+}
+
+impl AuthorAggregator {
+    pub fn new(idle: Duration) -> Self {
+        Self {
+            idle,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Adds a comment to its author's group. Returns what to analyze right away:
+    /// the comment alone if it can't be grouped (anonymous or an edit), or the
+    /// group once it is full.
+    pub fn push(&mut self, comment: RawComment, now: Instant) -> Option<Vec<RawComment>> {
+        let author = author_key(comment.author_id, comment.username.as_deref());
+        let Some(author) = author.filter(|_| !comment.edited) else {
+            return Some(vec![comment]);
+        };
+
+        let key = (comment.channel.clone(), author);
+        let group = self.groups.entry(key.clone()).or_insert_with(|| Group {
+            comments: Vec::new(),
+            last_at: now,
+        });
+        group.comments.push(comment);
+        group.last_at = now;
+        if group.comments.len() < MAX_COMMENTS {
+            return None;
+        }
+        self.groups.remove(&key).map(|g| g.comments)
+    }
+
+    /// When the next group goes idle, if any is waiting.
+    pub fn next_flush(&self) -> Option<Instant> {
+        self.groups.values().map(|g| g.last_at + self.idle).min()
+    }
+
+    /// Groups whose author has been quiet for the idle window.
+    pub fn take_idle(&mut self, now: Instant) -> Vec<Vec<RawComment>> {
+        let idle: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|(_, g)| g.last_at + self.idle <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        idle.into_iter()
+            .filter_map(|key| self.groups.remove(&key))
+            .map(|g| g.comments)
+            .collect()
+    }
+
+    /// Every waiting group (on shutdown).
+    pub fn drain(&mut self) -> Vec<Vec<RawComment>> {
+        self.groups.drain().map(|(_, g)| g.comments).collect()
+    }
+}
+
+/// The comment sent to the model for a group of two or more: the latest one,
+/// with every text in order and the IDs of all of them.
+pub fn merge(comments: &[RawComment]) -> RawComment {
+    let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+    let text = texts.join("\n\n");
+    let ids = comments.iter().map(|c| c.comment_id).collect();
+    let velocity = comments.iter().map(|c| c.comment_velocity).max().unwrap_or(0);
+    let same_post = comments.iter().all(|c| c.post_id == comments[0].post_id);
+
+    let mut merged = comments.last().expect("group is not empty").clone();
+    merged.text = text;
+    merged.aggregated_comment_ids = ids;
+    merged.comment_velocity = velocity;
+    // The latest post says nothing about comments under other posts
+    if !same_post {
+        merged.post_text.clear();
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::raw_comment;
+
+    fn comment(comment_id: i32, author_id: i64, text: &str) -> RawComment {
+        RawComment {
+            author_id,
+            text: text.to_string(),
+            post_text: "Пост".to_string(),
+            comment_velocity: comment_id as u32,
+            ..raw_comment(comment_id)
+        }
+    }
+
+    #[test]
+    fn merges_an_authors_comments_once_they_go_quiet() {
+        let idle = Duration::from_secs(30);
+        let start = Instant::now();
+        let mut authors = AuthorAggregator::new(idle);
+
+        assert!(authors.push(comment(1, 7, "У меня агентство"), start).is_none());
+        assert!(authors.push(comment(2, 8, "Красиво"), start).is_none());
+        let later = start + Duration::from_secs(20);
+        assert!(authors.push(comment(3, 7, "Ищем клиентов"), later).is_none());
+        assert_eq!(authors.push(comment(4, 0, "Аноним"), later).unwrap()[0].comment_id, 4);
+
+        let flushed = authors.take_idle(start + idle);
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].len(), 1);
+        assert_eq!(flushed[0][0].comment_id, 2);
+        assert_eq!(authors.next_flush(), Some(later + idle));
+
+        let group = authors.take_idle(later + idle).pop().unwrap();
+        assert_eq!(group.len(), 2);
+        let merged = merge(&group);
+        assert_eq!(merged.comment_id, 3);
+        assert_eq!(merged.aggregated_comment_ids, vec![1, 3]);
+        assert_eq!(merged.text, "У меня агентство\n\nИщем клиентов");
+        assert_eq!(merged.comment_velocity, 3);
+        assert_eq!(merged.post_text, "Пост");
+        assert!(authors.next_flush().is_none());
+    }
+}
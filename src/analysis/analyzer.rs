@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::telegram::RawComment;
+use super::types::{AnalyzedComment, Classification};
+use super::usage::UsageMeter;
+
+/// A classification backend (Gemini, OpenAI-compatible, ...).
+///
+/// Implementations make one request per call; concurrency limits, retries on
+/// the pipeline level and journaling live in `AnalysisPipeline`.
+#[async_trait]
+pub trait Analyzer: Send + Sync {
+    /// Short backend name for logs, e.g. "Gemini"
+    fn name(&self) -> &'static str;
+
+    /// Token totals of this backend's requests
+    fn usage(&self) -> &UsageMeter;
+
+    async fn analyze(&self, comment: &RawComment) -> Result<AnalyzedComment>;
+
+    /// Classifies several comments, one result per comment in order. The
+    /// default analyzes them one by one.
+    async fn analyze_batch(&self, comments: &[RawComment]) -> Vec<Result<AnalyzedComment>> {
+        analyze_each(self, comments).await
+    }
+}
+
+/// One `analyze` call per comment; also the fallback when a batch answer
+/// can't be matched to its comments.
+pub async fn analyze_each<A: Analyzer + ?Sized>(
+    analyzer: &A,
+    comments: &[RawComment],
+) -> Vec<Result<AnalyzedComment>> {
+    let mut results = Vec::with_capacity(comments.len());
+    for comment in comments {
+        results.push(analyzer.analyze(comment).await);
+    }
+    results
+}
+
+/// Joins a parsed batch answer with its comments, falling back to
+/// `analyze_each` when it is unusable or has the wrong number of results.
+pub async fn finish_batch<A: Analyzer + ?Sized>(
+    analyzer: &A,
+    comments: &[RawComment],
+    answer: Result<Vec<Classification>>,
+) -> Vec<Result<AnalyzedComment>> {
+    match answer {
+        Ok(classifications) if classifications.len() == comments.len() => comments
+            .iter()
+            .zip(classifications)
+            .map(|(comment, c)| Ok(AnalyzedComment::new(comment, c)))
+            .collect(),
+        Ok(classifications) => {
+            warn!(
+                "{} returned {} results for a batch of {}, analyzing one by one",
+                analyzer.name(),
+                classifications.len(),
+                comments.len()
+            );
+            analyze_each(analyzer, comments).await
+        }
+        Err(e) => {
+            warn!("Unusable {} batch answer, analyzing one by one: {:#}", analyzer.name(), e);
+            analyze_each(analyzer, comments).await
+        }
+    }
+}
+
+/// The same request error for each of `count` comments.
+pub fn fail_all(count: usize, error: anyhow::Error) -> Vec<Result<AnalyzedComment>> {
+    (0..count).map(|_| Err(anyhow!("{:#}", error))).collect()
+}
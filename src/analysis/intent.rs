@@ -1,76 +1,217 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
 
-/// Intent categories for B2B lead identification (smart Telegram monitoring service).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum Intent {
-    /// Business owner, co-founder, CEO, entrepreneur
-    BusinessOwner,
-    /// Marketer, sales manager, lead gen specialist
-    Marketer,
-    /// Realtor or real estate agency representative
-    RealtorAgency,
-    /// Property investor managing a portfolio at scale
-    Investor,
-    /// IT/tech business — potential partner or referral
-    ItBusiness,
-    /// Person expresses a pain point Telegram monitoring solves
-    PainSignal,
-    /// Regular individual (not a business lead)
-    Individual,
-    /// Neutral comment, no business context
-    Neutral,
-    /// Spam or irrelevant content
-    Spam,
+/// One classification category, from `[[gemini.categories]]` in config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Category {
+    /// Snake-case identifier, as used by the model and in config
+    pub key: String,
+    /// Display name on the dashboard and in notifications
+    pub label: String,
+    /// Badge class from style.css, e.g. `intent-problem`
+    pub css_class: String,
+    /// What the category means, for the model prompt
+    #[serde(default)]
+    pub description: String,
+    /// Comments in this category are leads whatever the model says about is_lead
+    /// (storage.lead_intents can still exclude them)
+    #[serde(default)]
+    pub always_lead: bool,
+}
+
+/// Active category set; None until `configure` is called (= the defaults).
+static CATEGORIES: RwLock<Option<Arc<[Category]>>> = RwLock::new(None);
+
+/// Replaces the category set (an empty list restores the defaults).
+pub fn configure(categories: &[Category]) {
+    let set = (!categories.is_empty()).then(|| Arc::from(categories));
+    *CATEGORIES.write().unwrap() = set;
 }
 
+/// The configured categories, or the built-in B2B lead set.
+pub fn categories() -> Arc<[Category]> {
+    if let Some(set) = CATEGORIES.read().unwrap().as_ref() {
+        return set.clone();
+    }
+    default_categories()
+}
+
+/// A comment's intent: the key of one of the configured categories.
+///
+/// Keys are checked against the category set where they come from config or
+/// the model (`Intent::known`, `Intent::from_model`); results loaded from disk
+/// may carry keys of an older set and are shown as-is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Intent(String);
+
 impl Intent {
-    pub fn label(&self) -> &'static str {
-        match self {
-            Intent::BusinessOwner => "Владелец бизнеса",
-            Intent::Marketer => "Маркетолог / продажи",
-            Intent::RealtorAgency => "Риэлтор / агентство",
-            Intent::Investor => "Инвестор",
-            Intent::ItBusiness => "IT / технологии",
-            Intent::PainSignal => "Боль бизнеса",
-            Intent::Individual => "Физлицо",
-            Intent::Neutral => "Нейтрально",
-            Intent::Spam => "Спам",
-        }
+    /// The intent for a configured key (case-insensitive), if there is one.
+    pub fn known(key: &str) -> Option<Intent> {
+        let key = key.trim().to_lowercase();
+        categories()
+            .iter()
+            .any(|c| c.key == key)
+            .then_some(Intent(key))
+    }
+
+    /// Maps a model answer to a category; unknown keys become `neutral` (or the
+    /// last category if the set has no `neutral`).
+    pub fn from_model(key: &str) -> Intent {
+        Intent::known(key).unwrap_or_else(Intent::fallback)
+    }
+
+    /// Used when a comment couldn't be classified.
+    pub fn fallback() -> Intent {
+        let categories = categories();
+        let key = categories
+            .iter()
+            .find(|c| c.key == "neutral")
+            .or(categories.last())
+            .map(|c| c.key.clone())
+            .unwrap_or_else(|| "neutral".to_string());
+        Intent(key)
+    }
+
+    pub fn label(&self) -> String {
+        self.with_category(|c| c.label.clone())
+            .unwrap_or_else(|| self.0.clone())
+    }
+
+    /// Snake-case identifier, as used by the model and in config
+    pub fn key(&self) -> &str {
+        &self.0
     }
 
-    pub fn css_class(&self) -> &'static str {
-        match self {
-            Intent::BusinessOwner => "intent-buying",
-            Intent::Marketer => "intent-help",
-            Intent::RealtorAgency => "intent-question",
-            Intent::Investor => "intent-feedback",
-            Intent::ItBusiness => "intent-help",
-            Intent::PainSignal => "intent-problem",
-            Intent::Individual => "intent-neutral",
-            Intent::Neutral => "intent-neutral",
-            Intent::Spam => "intent-spam",
-        }
+    pub fn css_class(&self) -> String {
+        self.with_category(|c| c.css_class.clone())
+            .unwrap_or_else(|| "intent-neutral".to_string())
     }
 
-    pub fn all() -> &'static [Intent] {
-        &[
-            Intent::BusinessOwner,
-            Intent::Marketer,
-            Intent::RealtorAgency,
-            Intent::Investor,
-            Intent::ItBusiness,
-            Intent::PainSignal,
-            Intent::Individual,
-            Intent::Neutral,
-            Intent::Spam,
-        ]
+    /// Whether the category makes every comment in it a lead.
+    pub fn always_lead(&self) -> bool {
+        self.with_category(|c| c.always_lead).unwrap_or(false)
+    }
+
+    pub fn all() -> Vec<Intent> {
+        categories().iter().map(|c| Intent(c.key.clone())).collect()
+    }
+
+    /// Applies `f` to this intent's category, if it is in the active set.
+    fn with_category<T>(&self, f: impl FnOnce(&Category) -> T) -> Option<T> {
+        categories().iter().find(|c| c.key == self.0).map(f)
     }
 }
 
 impl fmt::Display for Intent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.label())
+        f.write_str(&self.label())
+    }
+}
+
+/// Intent categories for B2B lead identification (smart Telegram monitoring service).
+fn default_categories() -> Arc<[Category]> {
+    static DEFAULTS: OnceLock<Arc<[Category]>> = OnceLock::new();
+    DEFAULTS.get_or_init(build_default_categories).clone()
+}
+
+fn build_default_categories() -> Arc<[Category]> {
+    let category = |key: &str, label: &str, css_class: &str, description: &str| Category {
+        key: key.to_string(),
+        label: label.to_string(),
+        css_class: css_class.to_string(),
+        description: description.to_string(),
+        always_lead: false,
+    };
+    Arc::from([
+        category(
+            "business_owner",
+            "Владелец бизнеса",
+            "intent-buying",
+            "Person identifies as owner, co-founder, CEO, entrepreneur, runs a business or agency",
+        ),
+        category(
+            "marketer",
+            "Маркетолог / продажи",
+            "intent-help",
+            "Person works in marketing, sales, lead generation, CRM, advertising — mentions campaigns, funnels, conversions",
+        ),
+        category(
+            "realtor_agency",
+            "Риэлтор / агентство",
+            "intent-question",
+            "Person is a realtor, broker, or represents a real estate agency — sells or rents multiple properties",
+        ),
+        category(
+            "investor",
+            "Инвестор",
+            "intent-feedback",
+            "Person buys multiple properties, manages a portfolio, discusses investment at scale",
+        ),
+        category(
+            "it_business",
+            "IT / технологии",
+            "intent-help",
+            "Person builds products, works in tech, SaaS, automation — could be a partner or referral",
+        ),
+        category(
+            "pain_signal",
+            "Боль бизнеса",
+            "intent-problem",
+            "Person expresses a clear business pain that Telegram monitoring could solve (e.g. \"can't find clients\", \"need to track competitors\", \"tired of manual monitoring\")",
+        ),
+        // Hiring is a growth signal
+        Category {
+            always_lead: true,
+            ..category(
+                "hiring",
+                "Найм сотрудников",
+                "intent-hiring",
+                "Person is looking to hire staff or recruit (\"ищу сотрудника\", \"нужен менеджер в команду\") — a sign of a growing business",
+            )
+        },
+        category(
+            "individual",
+            "Физлицо",
+            "intent-neutral",
+            "Regular person — buying/renting for themselves, discussing their own apartment",
+        ),
+        category(
+            "neutral",
+            "Нейтрально",
+            "intent-neutral",
+            "General comment, reaction, no business context",
+        ),
+        category("spam", "Спам", "intent-spam", "Spam, bots, ads"),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_categories_replace_the_defaults() {
+        // Other tests share the global set, so keep the defaults in it
+        let mut custom = default_categories().to_vec();
+        custom.push(Category {
+            key: "tender".to_string(),
+            label: "Тендер".to_string(),
+            css_class: "intent-buying".to_string(),
+            description: "Announces a tender or request for proposals".to_string(),
+            always_lead: true,
+        });
+        configure(&custom);
+
+        let tender = Intent::known("Tender").unwrap();
+        assert_eq!(tender.label(), "Тендер");
+        assert_eq!(tender.css_class(), "intent-buying");
+        assert!(tender.always_lead());
+        assert!(Intent::all().contains(&tender));
+
+        configure(&[]);
+        assert!(Intent::known("tender").is_none());
+        assert_eq!(Intent::from_model("tender").key(), "neutral");
     }
 }
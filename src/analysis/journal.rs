@@ -0,0 +1,292 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, warn};
+
+use crate::storage::write_atomic;
+use crate::telegram::RawComment;
+
+type CommentKey = (String, i32, i32);
+
+/// The log is compacted once it has this many lines per pending comment (and
+/// at least `MIN_COMPACT_LINES`), so each change costs amortized O(1) disk I/O.
+const COMPACT_RATIO: usize = 4;
+const MIN_COMPACT_LINES: usize = 256;
+
+/// Write-ahead journal of comments handed to the analyzer but not yet broadcast.
+///
+/// Entries are added before the Gemini call and removed once the result is sent
+/// on, so comments in flight during a crash are replayed on the next start.
+/// Changes are appended to `pending.jsonl`; the log is rewritten atomically with
+/// just the pending comments once it has grown well past them, or emptied once
+/// nothing is pending. A full journal takes no new comments until results come
+/// back (see `has_room`), so nothing in it is ever evicted.
+pub struct Journal {
+    path: PathBuf,
+    max_entries: usize,
+    pending: Mutex<Pending>,
+    /// Signalled when comments are completed, for `wait_for_room`
+    room: Notify,
+}
+
+/// One line of the log.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    /// Handed to the analyzer
+    Add(RawComment),
+    /// Result broadcast, no longer pending
+    Done(CommentKey),
+}
+
+#[derive(Default)]
+struct Pending {
+    /// key → (order added, comment)
+    entries: HashMap<CommentKey, (u64, RawComment)>,
+    next_seq: u64,
+    /// Lines in the log, including those of completed comments
+    log_lines: usize,
+}
+
+impl Pending {
+    fn insert(&mut self, comment: RawComment) {
+        self.entries.insert(key_of(&comment), (self.next_seq, comment));
+        self.next_seq += 1;
+    }
+
+    /// Pending comments, oldest first.
+    fn in_order(&self) -> Vec<RawComment> {
+        let mut entries: Vec<&(u64, RawComment)> = self.entries.values().collect();
+        entries.sort_by_key(|(seq, _)| *seq);
+        entries.into_iter().map(|(_, c)| c.clone()).collect()
+    }
+}
+
+impl Journal {
+    /// Opens `pending.jsonl` in `data_dir`, loading any entries left by a previous
+    /// run. A torn last line (a crash mid-append) is skipped; a log that doesn't
+    /// parse otherwise is moved aside to `pending.jsonl.bad` and the journal
+    /// starts empty.
+    pub fn open(data_dir: PathBuf, max_entries: usize) -> Self {
+        let path = data_dir.join("pending.jsonl");
+        let pending = match std::fs::read_to_string(&path) {
+            Ok(text) => match replay(&text) {
+                Ok(pending) => pending,
+                Err(e) => {
+                    let mut bad = path.as_os_str().to_owned();
+                    bad.push(".bad");
+                    warn!("{} is not a valid analysis journal ({:#}), moving it aside", path.display(), e);
+                    if let Err(e) = std::fs::rename(&path, &bad) {
+                        error!("Failed to move {} aside: {}", path.display(), e);
+                    }
+                    Pending::default()
+                }
+            },
+            Err(_) => Pending::default(),
+        };
+
+        Self {
+            path,
+            max_entries: max_entries.max(1),
+            pending: Mutex::new(pending),
+            room: Notify::new(),
+        }
+    }
+
+    /// Comments that were in flight when the previous run stopped, oldest first.
+    pub async fn pending(&self) -> Vec<RawComment> {
+        self.pending.lock().await.in_order()
+    }
+
+    /// Whether `comment` can be added without going over `max_entries`. Comments
+    /// already pending (replayed ones) always can.
+    pub async fn has_room(&self, comment: &RawComment) -> bool {
+        let pending = self.pending.lock().await;
+        pending.entries.len() < self.max_entries || pending.entries.contains_key(&key_of(comment))
+    }
+
+    /// Waits until a comment can be added. For a single caller (the pipeline's
+    /// receive loop), which must have sent everything it journaled on for analysis.
+    pub async fn wait_for_room(&self) {
+        while self.pending.lock().await.entries.len() >= self.max_entries {
+            self.room.notified().await;
+        }
+    }
+
+    /// Records a comment about to be analyzed; check `has_room` first.
+    pub async fn add(&self, comment: &RawComment) {
+        let mut pending = self.pending.lock().await;
+        if pending.entries.contains_key(&key_of(comment)) {
+            return;
+        }
+        pending.insert(comment.clone());
+        self.append(&mut pending, vec![Record::Add(comment.clone())]).await;
+    }
+
+    /// Removes the comment, and every comment aggregated into it.
+    pub async fn complete(&self, comment: &RawComment) {
+        let mut pending = self.pending.lock().await;
+        let mut done = Vec::new();
+        let key = key_of(comment);
+        if pending.entries.remove(&key).is_some() {
+            done.push(key);
+        }
+        if !comment.aggregated_comment_ids.is_empty() {
+            let aggregated: Vec<CommentKey> = pending
+                .entries
+                .keys()
+                .filter(|(channel, _, id)| {
+                    *channel == comment.channel && comment.aggregated_comment_ids.contains(id)
+                })
+                .cloned()
+                .collect();
+            for key in aggregated {
+                pending.entries.remove(&key);
+                done.push(key);
+            }
+        }
+        if done.is_empty() {
+            return;
+        }
+
+        let compact_at = (pending.entries.len() * COMPACT_RATIO).max(MIN_COMPACT_LINES);
+        if pending.entries.is_empty() || pending.log_lines + done.len() > compact_at {
+            self.compact(&mut pending).await;
+        } else {
+            self.append(&mut pending, done.into_iter().map(Record::Done).collect()).await;
+        }
+        self.room.notify_one();
+    }
+
+    async fn append(&self, pending: &mut Pending, records: Vec<Record>) {
+        let result = async {
+            let mut lines = String::new();
+            for record in &records {
+                lines.push_str(&serde_json::to_string(record).context("Failed to serialize journal entry")?);
+                lines.push('\n');
+            }
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .context("Failed to open pending.jsonl")?;
+            file.write_all(lines.as_bytes())
+                .await
+                .context("Failed to write to pending.jsonl")?;
+            anyhow::Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => pending.log_lines += records.len(),
+            Err(e) => error!("Failed to update analysis journal: {:#}", e),
+        }
+    }
+
+    /// Rewrites the log with only the pending comments.
+    async fn compact(&self, pending: &mut Pending) {
+        let result = async {
+            let mut lines = String::new();
+            for comment in pending.in_order() {
+                lines.push_str(&serde_json::to_string(&Record::Add(comment)).context("Failed to serialize journal")?);
+                lines.push('\n');
+            }
+            write_atomic(&self.path, lines.as_bytes()).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => pending.log_lines = pending.entries.len(),
+            Err(e) => error!("Failed to compact analysis journal: {:#}", e),
+        }
+    }
+}
+
+/// Folds the log's lines into the comments still pending.
+fn replay(text: &str) -> Result<Pending> {
+    let mut pending = Pending::default();
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    for (i, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(Record::Add(comment)) => pending.insert(comment),
+            Ok(Record::Done(key)) => {
+                pending.entries.remove(&key);
+            }
+            Err(_) if i + 1 == lines.len() && !text.ends_with('\n') => {
+                warn!("Skipping the analysis journal's unfinished last line");
+                continue;
+            }
+            Err(e) => return Err(e).with_context(|| format!("line {}", i + 1)),
+        }
+        pending.log_lines += 1;
+    }
+    Ok(pending)
+}
+
+fn key_of(c: &RawComment) -> CommentKey {
+    (c.channel.clone(), c.post_id, c.comment_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::raw_comment as comment;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("atento-journal-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn replays_only_unfinished_comments_in_order() {
+        let dir = temp_dir("replay");
+        let journal = Journal::open(dir.clone(), 10);
+        for id in [3, 1, 2] {
+            journal.add(&comment(id)).await;
+        }
+        journal.add(&comment(3)).await;
+        journal.complete(&comment(1)).await;
+
+        let ids: Vec<i32> = Journal::open(dir, 10).pending().await.iter().map(|c| c.comment_id).collect();
+        assert_eq!(ids, vec![3, 2]);
+    }
+
+    #[tokio::test]
+    async fn corrupt_log_is_moved_aside() {
+        let dir = temp_dir("corrupt");
+        std::fs::write(dir.join("pending.jsonl"), "{\"add\": 1}\n{\"done\": [\"chan\", 1, 1]}\n").unwrap();
+
+        assert!(Journal::open(dir.clone(), 10).pending().await.is_empty());
+        assert!(dir.join("pending.jsonl.bad").exists());
+    }
+
+    #[tokio::test]
+    async fn torn_last_line_is_skipped() {
+        let dir = temp_dir("torn");
+        let line = serde_json::to_string(&Record::Add(comment(1))).unwrap();
+        std::fs::write(dir.join("pending.jsonl"), format!("{}\n{{\"add\": {{\"chan", line)).unwrap();
+
+        assert_eq!(Journal::open(dir, 10).pending().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn full_journal_waits_instead_of_evicting() {
+        let dir = temp_dir("full");
+        let journal = Journal::open(dir, 2);
+        journal.add(&comment(1)).await;
+        journal.add(&comment(2)).await;
+
+        assert!(!journal.has_room(&comment(3)).await);
+        assert!(journal.has_room(&comment(2)).await);
+        journal.complete(&comment(1)).await;
+        journal.wait_for_room().await;
+        assert!(journal.has_room(&comment(3)).await);
+        assert_eq!(journal.pending().await.len(), 1);
+    }
+}
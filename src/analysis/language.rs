@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tracing::{debug, info};
+use whatlang::Lang;
+
+/// Skips between two log lines with the running totals
+const LOG_EVERY: u64 = 100;
+
+/// Keeps comments in other languages away from the model, counting what it skips.
+pub struct LanguageFilter {
+    allowed: Vec<Lang>,
+    /// Skipped comments per detected language code since startup
+    skipped: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl LanguageFilter {
+    /// None for an empty allowlist (filter off). Codes are validated with the config.
+    pub fn new(languages: &[String]) -> Option<Self> {
+        let allowed: Vec<Lang> = languages.iter().filter_map(Lang::from_code).collect();
+        (!allowed.is_empty()).then(|| Self {
+            allowed,
+            skipped: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    /// False if the text is reliably detected as a language outside the allowlist.
+    /// Short or mixed texts can't be detected reliably and are let through.
+    pub fn allows(&self, text: &str) -> bool {
+        let Some(info) = whatlang::detect(text) else {
+            return true;
+        };
+        if !info.is_reliable() || self.allowed.contains(&info.lang()) {
+            return true;
+        }
+
+        let code = info.lang().code();
+        debug!("Skipping comment in {}: {:?}", code, text);
+        let mut skipped = self.skipped.lock().unwrap();
+        *skipped.entry(code).or_insert(0) += 1;
+        let total: u64 = skipped.values().sum();
+        if total.is_multiple_of(LOG_EVERY) {
+            let by_lang: Vec<String> = skipped.iter().map(|(code, n)| format!("{} {}", code, n)).collect();
+            info!("Language filter skipped {} comments so far ({})", total, by_lang.join(", "));
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_only_reliable_detections_outside_the_allowlist() {
+        let filter = LanguageFilter::new(&["rus".to_string()]).unwrap();
+        assert!(filter.allows("Подскажите, пожалуйста, где найти хорошего бухгалтера для небольшой компании?"));
+        assert!(!filter.allows("Could anyone recommend a good accountant for a small business? We need help with taxes this year."));
+        assert!(filter.allows("ok"));
+        assert_eq!(filter.skipped.lock().unwrap().get("eng"), Some(&1));
+
+        assert!(LanguageFilter::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::config::GeminiConfig;
+use crate::telegram::RawComment;
+use super::analyzer::Analyzer;
+use super::intent::Intent;
+use super::types::{AnalyzedComment, Classification};
+use super::usage::UsageMeter;
+
+/// Keyword rules per built-in category, matched against the lowercased text.
+/// Rules for keys missing from the configured category set are skipped.
+const RULES: &[(&str, &[&str])] = &[
+    ("hiring", &["ищу сотрудник", "ищем сотрудник", "ваканси", "в команду", "hiring"]),
+    ("pain_signal", &["нет клиентов", "не могу найти клиент", "вручную", "устал", "не хватает заявок"]),
+    ("realtor_agency", &["риэлтор", "риелтор", "агентство недвижимости", "брокер"]),
+    ("investor", &["инвест", "портфель", "доходность"]),
+    ("marketer", &["маркетинг", "маркетолог", "реклам", "воронк", "лидоген", "crm"]),
+    ("it_business", &["saas", "разработ", "автоматизац", "стартап"]),
+    ("business_owner", &["мой бизнес", "владелец", "собственник", "основатель", "founder", "ceo"]),
+];
+
+/// Offline backend for trying the pipeline without an API key: assigns intents
+/// by keyword (see `RULES`), any match being a lead. Makes no requests.
+pub struct MockAnalyzer {
+    usage: UsageMeter,
+}
+
+impl MockAnalyzer {
+    pub fn new(config: &GeminiConfig) -> Self {
+        Self { usage: UsageMeter::new(config) }
+    }
+}
+
+/// The first rule with a keyword in `text`, as (intent, keyword).
+fn classify(text: &str) -> Option<(Intent, &'static str)> {
+    let text = text.to_lowercase();
+    RULES.iter().find_map(|(key, keywords)| {
+        let intent = Intent::known(key)?;
+        keywords.iter().find(|k| text.contains(*k)).map(|k| (intent, *k))
+    })
+}
+
+#[async_trait]
+impl Analyzer for MockAnalyzer {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn usage(&self) -> &UsageMeter {
+        &self.usage
+    }
+
+    async fn analyze(&self, comment: &RawComment) -> Result<AnalyzedComment> {
+        let classification = match classify(&comment.text) {
+            Some((intent, keyword)) => Classification {
+                sentiment: if intent.key() == "pain_signal" { -0.5 } else { 0.0 },
+                intent,
+                confidence: 0.5,
+                is_lead: true,
+                lead_score: 0.6,
+                need_summary: format!("[mock] ключевое слово «{}»", keyword),
+            },
+            None => Classification {
+                confidence: 0.5,
+                ..Classification::neutral()
+            },
+        };
+        Ok(AnalyzedComment::new(comment, classification))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_first_matching_rule() {
+        let (intent, keyword) = classify("Ищем сотрудников в команду, у нас свой SaaS").unwrap();
+        assert_eq!(intent.key(), "hiring");
+        assert_eq!(keyword, "ищем сотрудник");
+
+        assert_eq!(classify("Я владелец агентства, CRM не спасает").unwrap().0.key(), "marketer");
+        assert!(classify("Отличный дом, спасибо!").is_none());
+    }
+}
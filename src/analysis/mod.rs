@@ -1,7 +1,36 @@
+pub mod aggregate;
+pub mod analyzer;
 pub mod gemini;
 pub mod intent;
+pub mod journal;
+pub mod language;
+pub mod mock;
+pub mod openai;
+pub mod pipeline;
+pub mod prefilter;
+pub mod prompt;
+pub mod text_cache;
 pub mod types;
+pub mod usage;
 
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::config::{GeminiConfig, Provider};
+
+pub use analyzer::Analyzer;
 pub use gemini::GeminiAnalyzer;
 pub use intent::Intent;
+pub use mock::MockAnalyzer;
+pub use openai::OpenAiAnalyzer;
+pub use pipeline::AnalysisPipeline;
 pub use types::AnalyzedComment;
+
+/// The backend selected by `gemini.provider`.
+pub fn build_analyzer(config: &GeminiConfig) -> Result<Arc<dyn Analyzer>> {
+    Ok(match config.provider {
+        Provider::Gemini => Arc::new(GeminiAnalyzer::new(config)?),
+        Provider::OpenAi => Arc::new(OpenAiAnalyzer::new(config)?),
+        Provider::Mock => Arc::new(MockAnalyzer::new(config)),
+    })
+}
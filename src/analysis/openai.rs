@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::GeminiConfig;
+use crate::telegram::RawComment;
+use super::analyzer::{fail_all, finish_batch, Analyzer};
+use super::prompt::{
+    batch_prompt, comment_prompt, http_client, parse_batch, parse_classification, request_timeout, send_with_retry,
+    system_prompt,
+};
+use super::types::AnalyzedComment;
+use super::usage::UsageMeter;
+
+/// OpenAI-compatible `/chat/completions` backend (OpenAI, Ollama, vLLM, ...).
+pub struct OpenAiAnalyzer {
+    client: Client,
+    /// Deadline for one attempt, also enforced outside the client
+    request_timeout: Duration,
+    /// Empty for servers that don't need one (e.g. local Ollama)
+    api_key: String,
+    model: String,
+    /// API root including the version, e.g. `https://api.openai.com/v1`
+    base_url: String,
+    max_post_context_chars: usize,
+    temperature: f32,
+    /// Per comment; batches get this times the batch size
+    max_output_tokens: u32,
+    usage: UsageMeter,
+    /// Built once from the config; see `prompt::system_prompt`
+    system_prompt: String,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+    max_tokens: u32,
+    response_format: ResponseFormat,
+}
+
+#[derive(Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Deserialize)]
+struct ChoiceMessage {
+    content: Option<String>,
+}
+
+impl OpenAiAnalyzer {
+    pub fn new(config: &GeminiConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client(config)?,
+            request_timeout: request_timeout(config),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            max_post_context_chars: config.max_post_context_chars,
+            temperature: config.temperature,
+            max_output_tokens: config.max_output_tokens,
+            usage: UsageMeter::new(config),
+            system_prompt: system_prompt(&config.prompt_intro, &config.lead_criteria),
+        })
+    }
+
+    /// Sends the system prompt plus one user message and returns the model's answer.
+    async fn complete(&self, user: String, max_tokens: u32) -> Result<String> {
+        let url = format!("{}/chat/completions", self.base_url);
+
+        let request = ChatRequest {
+            model: &self.model,
+            messages: vec![
+                ChatMessage {
+                    role: "system",
+                    content: self.system_prompt.clone(),
+                },
+                ChatMessage {
+                    role: "user",
+                    content: user,
+                },
+            ],
+            temperature: self.temperature,
+            max_tokens,
+            response_format: ResponseFormat { kind: "json_object" },
+        };
+
+        let response = send_with_retry("OpenAI", self.request_timeout, || {
+            let builder = self.client.post(&url).json(&request);
+            if self.api_key.is_empty() {
+                builder
+            } else {
+                builder.bearer_auth(&self.api_key)
+            }
+        })
+        .await?;
+
+        let chat: ChatResponse = response
+            .json()
+            .await
+            .context("Failed to parse OpenAI response")?;
+
+        if let Some(usage) = &chat.usage {
+            self.usage.record(usage.prompt_tokens, usage.completion_tokens);
+        }
+
+        chat.choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .context("Empty OpenAI response")
+    }
+}
+
+#[async_trait]
+impl Analyzer for OpenAiAnalyzer {
+    fn name(&self) -> &'static str {
+        "OpenAI"
+    }
+
+    fn usage(&self) -> &UsageMeter {
+        &self.usage
+    }
+
+    async fn analyze(&self, comment: &RawComment) -> Result<AnalyzedComment> {
+        let user = comment_prompt(comment, self.max_post_context_chars);
+        let text = self.complete(user, self.max_output_tokens).await?;
+        Ok(AnalyzedComment::new(comment, parse_classification(&text)?))
+    }
+
+    async fn analyze_batch(&self, comments: &[RawComment]) -> Vec<Result<AnalyzedComment>> {
+        let user = batch_prompt(comments, self.max_post_context_chars);
+        match self.complete(user, self.max_output_tokens * comments.len() as u32).await {
+            Ok(text) => finish_batch(self, comments, parse_batch(&text)).await,
+            Err(e) => fail_all(comments.len(), e),
+        }
+    }
+}
+
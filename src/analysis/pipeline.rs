@@ -0,0 +1,573 @@
+use anyhow::{anyhow, Context, Result};
+use tokio::sync::{broadcast, mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio_util::task::TaskTracker;
+use tracing::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{GeminiConfig, StorageConfig};
+use crate::storage::append_jsonl;
+use crate::telegram::RawComment;
+use super::aggregate::{self, AuthorAggregator};
+use super::analyzer::Analyzer;
+use super::intent::Intent;
+use super::journal::Journal;
+use super::language::LanguageFilter;
+use super::prefilter::Prefilter;
+use super::text_cache::TextCache;
+use super::types::{AnalyzedComment, Classification};
+use super::usage::UsageSnapshot;
+
+/// Low-confidence classifications between two log lines with the running total
+const LOG_LOW_CONFIDENCE_EVERY: u64 = 100;
+
+/// Feeds scraped comments through an `Analyzer` backend: concurrency limits,
+/// reaction, keyword and language filters, author cooldown and aggregation,
+/// journaling and orphan handling.
+pub struct AnalysisPipeline {
+    analyzer: Arc<dyn Analyzer>,
+    semaphore: Arc<Semaphore>,
+    /// Bounds spawned analysis tasks (and the comments they hold) as a whole
+    in_flight: Arc<Semaphore>,
+    /// Where to append results nobody received (None = drop them)
+    orphan_path: Option<PathBuf>,
+    /// Comments with fewer reactions are not sent to the model
+    min_reactions: u32,
+    /// Where to append comments skipped by `min_reactions` (None = drop them)
+    skipped_path: Option<PathBuf>,
+    /// Short or stop-listed comments are classified neutral without a call (None = off)
+    prefilter: Option<Prefilter>,
+    /// Comments in other languages are classified neutral without a call (None = off)
+    language_filter: Option<LanguageFilter>,
+    /// Write-ahead journal of in-flight comments (None = disabled)
+    journal: Option<Journal>,
+    /// Within this window an author's cached classification is reused (None = off)
+    author_cooldown: Option<Duration>,
+    /// An author's comments are classified together once they are quiet this long (None = off)
+    author_aggregation: Option<Duration>,
+    /// (channel, comment_id) of a merged comment in flight → the comments it stands for
+    aggregated: Mutex<HashMap<(String, i32), Vec<RawComment>>>,
+    /// Author key → (analyzed_at, last analysis)
+    author_cache: Mutex<HashMap<String, (Instant, AnalyzedComment)>>,
+    /// Classifications by comment text (None = off)
+    text_cache: Option<TextCache>,
+    /// Comments sent to the backend per request
+    batch_size: usize,
+    /// A partial batch is sent once its first comment has waited this long
+    batch_timeout: Duration,
+    /// lead_score at which a comment counts as a lead regardless of is_lead
+    lead_threshold: f32,
+    /// Classifications below this are neutral non-leads (0.0 = off)
+    min_confidence: f32,
+    /// Drop comments below `min_confidence` instead of broadcasting them
+    drop_low_confidence: bool,
+    /// Comments that fell below `min_confidence` since startup
+    low_confidence: AtomicU64,
+    /// Spawned analysis tasks, awaited before `run` returns
+    tasks: TaskTracker,
+}
+
+impl AnalysisPipeline {
+    pub fn new(
+        analyzer: Arc<dyn Analyzer>,
+        config: &GeminiConfig,
+        storage: &StorageConfig,
+    ) -> Result<Self> {
+        if storage.journal_max_entries > 0 || config.text_cache_size > 0 {
+            std::fs::create_dir_all(&storage.data_dir)
+                .context("Failed to create data directory")?;
+        }
+        let journal = if storage.journal_max_entries > 0 {
+            Some(Journal::open(storage.data_dir.clone(), storage.journal_max_entries))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            analyzer,
+            semaphore: Arc::new(Semaphore::new(config.max_concurrent)),
+            in_flight: Arc::new(Semaphore::new(config.max_in_flight.max(config.max_concurrent))),
+            orphan_path: storage
+                .persist_orphaned
+                .then(|| storage.data_dir.join("orphaned.jsonl")),
+            min_reactions: config.min_reactions,
+            skipped_path: storage
+                .store_skipped
+                .then(|| storage.data_dir.join("skipped.jsonl")),
+            prefilter: Prefilter::new(&config.prefilter).context("Invalid gemini.prefilter")?,
+            language_filter: LanguageFilter::new(&config.languages),
+            journal,
+            author_cooldown: (config.author_cooldown_secs > 0)
+                .then(|| Duration::from_secs(config.author_cooldown_secs)),
+            author_cache: Mutex::new(HashMap::new()),
+            author_aggregation: (config.author_aggregation_secs > 0)
+                .then(|| Duration::from_secs(config.author_aggregation_secs)),
+            aggregated: Mutex::new(HashMap::new()),
+            text_cache: (config.text_cache_size > 0)
+                .then(|| TextCache::open(storage.data_dir.clone(), config.text_cache_size)),
+            batch_size: config.batch_size.max(1),
+            batch_timeout: Duration::from_millis(config.batch_timeout_ms),
+            lead_threshold: config.lead_threshold,
+            min_confidence: config.min_confidence,
+            drop_low_confidence: config.drop_low_confidence,
+            low_confidence: AtomicU64::new(0),
+            tasks: TaskTracker::new(),
+        })
+    }
+
+    pub async fn run(
+        self: Arc<Self>,
+        mut rx: mpsc::Receiver<RawComment>,
+        tx: broadcast::Sender<AnalyzedComment>,
+    ) -> Result<()> {
+        info!(
+            "{} analyzer started (max_concurrent: {}, max_in_flight: {}, batch_size: {})",
+            self.analyzer.name(),
+            self.semaphore.available_permits(),
+            self.in_flight.available_permits(),
+            self.batch_size
+        );
+
+        let replay = match &self.journal {
+            Some(journal) => journal.pending().await,
+            None => Vec::new(),
+        };
+        if !replay.is_empty() {
+            info!("Replaying {} unfinished analyses from journal", replay.len());
+        }
+        let mut replay = replay.into_iter();
+
+        let mut batch = Batch::default();
+        let mut authors = self.author_aggregation.map(AuthorAggregator::new);
+
+        loop {
+            let wake_at = match (batch.deadline, authors.as_ref().and_then(|a| a.next_flush())) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let received = match (replay.next(), wake_at) {
+                (Some(c), _) => Some(c),
+                (None, Some(at)) => match tokio::time::timeout_at(at, rx.recv()).await {
+                    Ok(received) => received,
+                    Err(_) => {
+                        let now = tokio::time::Instant::now();
+                        for group in authors.as_mut().map(|a| a.take_idle(now)).unwrap_or_default() {
+                            self.enqueue_group(group, &mut batch, &tx).await?;
+                        }
+                        if batch.deadline.is_some_and(|at| at <= now) {
+                            let (comments, permits) = batch.take();
+                            self.spawn_batch(comments, permits, &tx);
+                        }
+                        continue;
+                    }
+                },
+                (None, None) => rx.recv().await,
+            };
+            let Some(comment) = received else {
+                break;
+            };
+
+            if comment.reaction_total() < self.min_reactions {
+                if let Some(path) = &self.skipped_path {
+                    if let Err(e) = append_jsonl(path, &comment).await {
+                        error!("Failed to store skipped comment: {:#}", e);
+                    }
+                }
+                self.settle_replayed(&comment).await;
+                continue;
+            }
+
+            if self.prefilter.as_ref().is_some_and(|f| !f.allows(&comment.text))
+                || self.language_filter.as_ref().is_some_and(|f| !f.allows(&comment.text))
+            {
+                let skipped = AnalyzedComment::new(&comment, Classification::neutral());
+                if let Err(e) = tx.send(skipped) {
+                    self.persist_orphan(&e.0).await;
+                }
+                self.settle_replayed(&comment).await;
+                continue;
+            }
+
+            if let Some(cached) = self.cached_for_author(&comment).or_else(|| self.cached_for_text(&comment)) {
+                if let Some(cached) = self.apply_confidence_floor(cached) {
+                    if let Err(e) = tx.send(cached) {
+                        self.persist_orphan(&e.0).await;
+                    }
+                }
+                self.settle_replayed(&comment).await;
+                continue;
+            }
+
+            if let Some(journal) = &self.journal {
+                if !journal.has_room(&comment).await {
+                    // Backpressure: send on everything journaled so far and take no
+                    // more comments until results come back
+                    warn!("Analysis journal full, waiting for in-flight analyses");
+                    for group in authors.as_mut().map(AuthorAggregator::drain).unwrap_or_default() {
+                        self.enqueue_group(group, &mut batch, &tx).await?;
+                    }
+                    let (comments, permits) = batch.take();
+                    self.spawn_batch(comments, permits, &tx);
+                    journal.wait_for_room().await;
+                }
+                journal.add(&comment).await;
+            }
+
+            match &mut authors {
+                Some(authors) => {
+                    if let Some(group) = authors.push(comment, tokio::time::Instant::now()) {
+                        self.enqueue_group(group, &mut batch, &tx).await?;
+                    }
+                }
+                None => self.enqueue(comment, &mut batch, &tx).await?,
+            }
+        }
+
+        for group in authors.as_mut().map(AuthorAggregator::drain).unwrap_or_default() {
+            self.enqueue_group(group, &mut batch, &tx).await?;
+        }
+        let (comments, permits) = batch.take();
+        self.spawn_batch(comments, permits, &tx);
+
+        // Input closed (shutdown): finish what was already sent to the backend
+        self.tasks.close();
+        if !self.tasks.is_empty() {
+            info!("Waiting for {} in-flight analyses", self.tasks.len());
+        }
+        self.tasks.wait().await;
+        Ok(())
+    }
+
+    /// Adds a comment to the batch being collected, sending it once full.
+    async fn enqueue(
+        self: &Arc<Self>,
+        comment: RawComment,
+        batch: &mut Batch,
+        tx: &broadcast::Sender<AnalyzedComment>,
+    ) -> Result<()> {
+        // Wait here (not in the task) so a backlog stays in the channel, not in memory
+        batch.permits.push(self.in_flight.clone().acquire_owned().await?);
+        batch.comments.push(comment);
+
+        if batch.comments.len() >= self.batch_size {
+            let (comments, permits) = batch.take();
+            self.spawn_batch(comments, permits, tx);
+        } else if batch.deadline.is_none() {
+            batch.deadline = Some(tokio::time::Instant::now() + self.batch_timeout);
+        }
+        Ok(())
+    }
+
+    /// Enqueues an author's comments as one merged comment, keeping the originals
+    /// for `split_group`. A single comment is enqueued as is.
+    async fn enqueue_group(
+        self: &Arc<Self>,
+        mut group: Vec<RawComment>,
+        batch: &mut Batch,
+        tx: &broadcast::Sender<AnalyzedComment>,
+    ) -> Result<()> {
+        if group.len() == 1 {
+            return self.enqueue(group.remove(0), batch, tx).await;
+        }
+        let merged = aggregate::merge(&group);
+        self.aggregated
+            .lock()
+            .unwrap()
+            .insert((merged.channel.clone(), merged.comment_id), group);
+        self.enqueue(merged, batch, tx).await
+    }
+
+    /// The records to broadcast for `comment`'s result: just `result`, or for a
+    /// merged comment one per original comment with the group's classification,
+    /// of which only the latest (carrying the group's IDs) can be a lead.
+    fn split_group(&self, comment: &RawComment, result: AnalyzedComment) -> Vec<AnalyzedComment> {
+        if comment.aggregated_comment_ids.is_empty() {
+            return vec![result];
+        }
+        let key = (comment.channel.clone(), comment.comment_id);
+        let Some(group) = self.aggregated.lock().unwrap().remove(&key) else {
+            return vec![result];
+        };
+
+        let classification = result.classification();
+        let last = group.len() - 1;
+        group
+            .iter()
+            .enumerate()
+            .map(|(i, original)| {
+                let mut analyzed = AnalyzedComment::new(original, classification.clone());
+                analyzed.analyzed_at = result.analyzed_at;
+                if i == last {
+                    analyzed.aggregated_comment_ids = comment.aggregated_comment_ids.clone();
+                } else {
+                    analyzed.is_lead = false;
+                }
+                analyzed
+            })
+            .collect()
+    }
+
+    /// Analyzes `comments` in a background task: one request for the whole
+    /// batch, or a plain `analyze` call for a single comment.
+    fn spawn_batch(
+        self: &Arc<Self>,
+        comments: Vec<RawComment>,
+        permits: Vec<OwnedSemaphorePermit>,
+        tx: &broadcast::Sender<AnalyzedComment>,
+    ) {
+        if comments.is_empty() {
+            return;
+        }
+        let pipeline = self.clone();
+        let tx = tx.clone();
+
+        self.tasks.spawn(async move {
+            let _permits = permits;
+            let results = if comments.len() == 1 {
+                vec![pipeline.analyze_limited(&comments[0]).await]
+            } else {
+                match pipeline.semaphore.acquire().await {
+                    Ok(_permit) => pipeline
+                        .analyzer
+                        .analyze_batch(&comments)
+                        .await
+                        .into_iter()
+                        .map(|r| r.map(|c| pipeline.apply_threshold(c)))
+                        .collect(),
+                    Err(e) => comments.iter().map(|_| Err(anyhow!("{}", e))).collect(),
+                }
+            };
+
+            for (comment, analyzed) in comments.iter().zip(results) {
+                pipeline.deliver(comment, analyzed, &tx).await;
+            }
+        });
+    }
+
+    /// Broadcasts one comment's result (or a neutral fallback on failure), split
+    /// back into its original comments if it was merged, and clears it from the
+    /// journal.
+    #[tracing::instrument(skip_all, fields(channel = %comment.channel, post_id = comment.post_id, comment_id = comment.comment_id))]
+    async fn deliver(
+        &self,
+        comment: &RawComment,
+        analyzed: Result<AnalyzedComment>,
+        tx: &broadcast::Sender<AnalyzedComment>,
+    ) {
+        match analyzed {
+            Ok(result) => {
+                self.remember_author(&result);
+                if let Some(cache) = &self.text_cache {
+                    // A combined text won't come again
+                    if comment.aggregated_comment_ids.is_empty() {
+                        cache.insert(&comment.text, &result.classification()).await;
+                    }
+                }
+                let results = self.split_group(comment, result);
+                for result in results.into_iter().filter_map(|r| self.apply_confidence_floor(r)) {
+                    if result.is_lead {
+                        info!(
+                            "LEAD found in @{}: [{}] {} — \"{}\"",
+                            result.channel, result.intent, result.author, result.need_summary
+                        );
+                    }
+                    if let Err(e) = tx.send(result) {
+                        warn!("No active receivers for analyzed comments");
+                        self.persist_orphan(&e.0).await;
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to analyze comment: {:#}", e);
+                let fallback = AnalyzedComment::new(comment, Classification::neutral());
+                for fallback in self.split_group(comment, fallback) {
+                    if let Err(e) = tx.send(fallback) {
+                        self.persist_orphan(&e.0).await;
+                    }
+                }
+            }
+        }
+
+        if let Some(journal) = &self.journal {
+            journal.complete(comment).await;
+        }
+    }
+
+    /// Clears a comment that was settled without a model call from the journal,
+    /// in case it is a replayed entry (e.g. one whose result was cached just
+    /// before a crash). A no-op for comments that were never journaled.
+    async fn settle_replayed(&self, comment: &RawComment) {
+        if let Some(journal) = &self.journal {
+            journal.complete(comment).await;
+        }
+    }
+
+    /// Returns the author's last classification re-stamped onto `comment`,
+    /// if they were analyzed within the cooldown window. Not for edits, whose
+    /// last classification is usually the pre-edit text's.
+    fn cached_for_author(&self, comment: &RawComment) -> Option<AnalyzedComment> {
+        if comment.edited {
+            return None;
+        }
+        let cooldown = self.author_cooldown?;
+        let key = author_key(comment.author_id, comment.username.as_deref())?;
+
+        let cache = self.author_cache.lock().unwrap();
+        let (at, last) = cache.get(&key)?;
+        if at.elapsed() >= cooldown {
+            return None;
+        }
+
+        Some(AnalyzedComment::new(comment, last.classification()))
+    }
+
+    /// Reuses the classification of an identical text seen before, if cached.
+    fn cached_for_text(&self, comment: &RawComment) -> Option<AnalyzedComment> {
+        let classification = self.text_cache.as_ref()?.get(&comment.text)?;
+        Some(AnalyzedComment::new(comment, classification))
+    }
+
+    /// Caches a fresh analysis for the author cooldown, dropping expired entries.
+    fn remember_author(&self, result: &AnalyzedComment) {
+        let Some(cooldown) = self.author_cooldown else {
+            return;
+        };
+        let Some(key) = result.author_key() else {
+            return;
+        };
+
+        let mut cache = self.author_cache.lock().unwrap();
+        cache.retain(|_, (at, _)| at.elapsed() < cooldown);
+        cache.insert(key, (Instant::now(), result.clone()));
+    }
+
+    /// Appends a result that had no broadcast receivers to orphaned.jsonl, if enabled.
+    async fn persist_orphan(&self, comment: &AnalyzedComment) {
+        let Some(path) = &self.orphan_path else {
+            return;
+        };
+
+        match append_jsonl(path, comment).await {
+            Ok(()) => info!("Persisted orphaned result for comment {} to {}", comment.comment_id, path.display()),
+            Err(e) => error!("Failed to persist orphaned result: {:#}", e),
+        }
+    }
+
+    /// `analyze` under the pipeline's concurrency limit, for callers outside `run`.
+    pub async fn analyze_limited(&self, comment: &RawComment) -> Result<AnalyzedComment> {
+        let _permit = self.semaphore.acquire().await?;
+        self.analyze(comment).await
+    }
+
+    /// One backend call, bypassing the concurrency limit and author cache.
+    #[tracing::instrument(skip_all, fields(channel = %comment.channel, post_id = comment.post_id, comment_id = comment.comment_id))]
+    pub async fn analyze(&self, comment: &RawComment) -> Result<AnalyzedComment> {
+        let analyzed = self.analyzer.analyze(comment).await?;
+        Ok(self.apply_threshold(analyzed))
+    }
+
+    /// Backend token usage since startup.
+    pub fn usage(&self) -> UsageSnapshot {
+        UsageSnapshot {
+            low_confidence: self.low_confidence.load(Ordering::Relaxed),
+            ..self.analyzer.usage().snapshot()
+        }
+    }
+
+    /// A backend result below `min_confidence` becomes a neutral non-lead, or
+    /// None with `drop_low_confidence`. Caches keep the original, so they follow
+    /// config changes.
+    fn apply_confidence_floor(&self, mut analyzed: AnalyzedComment) -> Option<AnalyzedComment> {
+        if analyzed.confidence >= self.min_confidence {
+            return Some(analyzed);
+        }
+
+        debug!(
+            "Low-confidence ({:.2}) {} for comment {} in @{}",
+            analyzed.confidence, analyzed.intent, analyzed.comment_id, analyzed.channel
+        );
+        let total = self.low_confidence.fetch_add(1, Ordering::Relaxed) + 1;
+        if total.is_multiple_of(LOG_LOW_CONFIDENCE_EVERY) {
+            info!("{} classifications below min_confidence so far", total);
+        }
+        if self.drop_low_confidence {
+            return None;
+        }
+
+        analyzed.intent = Intent::fallback();
+        analyzed.is_lead = false;
+        analyzed.lead_score = 0.0;
+        analyzed.need_summary.clear();
+        Some(analyzed)
+    }
+
+    /// Borderline comments (is_lead=false but a high lead_score) become leads.
+    fn apply_threshold(&self, mut analyzed: AnalyzedComment) -> AnalyzedComment {
+        analyzed.is_lead |= analyzed.lead_score >= self.lead_threshold;
+        analyzed
+    }
+}
+
+/// Comments collected for the next backend request, with their in-flight permits.
+#[derive(Default)]
+struct Batch {
+    comments: Vec<RawComment>,
+    permits: Vec<OwnedSemaphorePermit>,
+    /// When a partial batch is sent anyway
+    deadline: Option<tokio::time::Instant>,
+}
+
+impl Batch {
+    /// Empties the batch for sending.
+    fn take(&mut self) -> (Vec<RawComment>, Vec<OwnedSemaphorePermit>) {
+        self.deadline = None;
+        (std::mem::take(&mut self.comments), std::mem::take(&mut self.permits))
+    }
+}
+
+/// Same identity as `AnalyzedComment::author_key`, for a comment not yet analyzed.
+pub(super) fn author_key(author_id: i64, username: Option<&str>) -> Option<String> {
+    if author_id != 0 {
+        Some(author_id.to_string())
+    } else {
+        username.map(|u| u.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::mock::MockAnalyzer;
+    use crate::analysis::types::fixtures::raw_comment;
+
+    #[tokio::test]
+    async fn replayed_comment_answered_from_cache_leaves_the_journal() {
+        let dir = std::env::temp_dir().join(format!("atento-pipeline-replay-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let gemini: GeminiConfig = toml::from_str(
+            "model = \"mock\"\nmax_concurrent = 1\nbase_url = \"https://example.invalid\"\n\
+             text_cache_size = 10\nlanguages = []",
+        )
+        .unwrap();
+        let storage: StorageConfig =
+            toml::from_str(&format!("data_dir = {:?}\nformat = \"jsonl\"", dir)).unwrap();
+        let pipeline = AnalysisPipeline::new(Arc::new(MockAnalyzer::new(&gemini)), &gemini, &storage).unwrap();
+
+        // A crash after the result was cached but before the journal was cleared
+        let mut comment = raw_comment(1);
+        comment.text = "Ищем сотрудников в отдел продаж".to_string();
+        let classification = Classification { confidence: 0.9, ..Classification::neutral() };
+        pipeline.text_cache.as_ref().unwrap().insert(&comment.text, &classification).await;
+        pipeline.journal.as_ref().unwrap().add(&comment).await;
+
+        let (_, rx) = mpsc::channel(1);
+        let (tx, mut results) = broadcast::channel(4);
+        Arc::new(pipeline).run(rx, tx).await.unwrap();
+
+        assert_eq!(results.recv().await.unwrap().comment_id, 1);
+        assert!(Journal::open(dir, 10).pending().await.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{debug, info};
+
+use crate::config::PrefilterConfig;
+
+/// Skips between two log lines with the running total
+const LOG_EVERY: u64 = 100;
+
+/// Catches obvious non-leads ("спасибо", "👍", one-word reactions) before they
+/// reach the model.
+pub struct Prefilter {
+    min_length: usize,
+    /// `stop_patterns`, anchored to match the whole comment
+    stop_patterns: Vec<Regex>,
+    skipped: AtomicU64,
+}
+
+impl Prefilter {
+    /// None when no rule is configured; fails on an invalid pattern.
+    pub fn new(config: &PrefilterConfig) -> Result<Option<Self>> {
+        if config.min_length == 0 && config.stop_patterns.is_empty() {
+            return Ok(None);
+        }
+        let stop_patterns = config
+            .stop_patterns
+            .iter()
+            .map(|p| Regex::new(&format!("(?i)^(?:{})$", p)).with_context(|| format!("Bad stop pattern `{}`", p)))
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            min_length: config.min_length,
+            stop_patterns,
+            skipped: AtomicU64::new(0),
+        }))
+    }
+
+    /// False if the comment is too short or matches a stop pattern.
+    pub fn allows(&self, text: &str) -> bool {
+        let text = text.trim();
+        if text.chars().count() >= self.min_length && !self.stop_patterns.iter().any(|p| p.is_match(text)) {
+            return true;
+        }
+
+        debug!("Prefilter skipped comment: {:?}", text);
+        let total = self.skipped.fetch_add(1, Ordering::Relaxed) + 1;
+        if total.is_multiple_of(LOG_EVERY) {
+            info!("Prefilter skipped {} comments so far", total);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_short_and_stop_listed_comments() {
+        let config = PrefilterConfig {
+            min_length: 4,
+            stop_patterns: vec!["спасибо!*".to_string(), "[+👍🔥 ]+".to_string()],
+        };
+        let filter = Prefilter::new(&config).unwrap().unwrap();
+
+        assert!(!filter.allows("  ок "));
+        assert!(!filter.allows("Спасибо!!"));
+        assert!(!filter.allows("👍👍 🔥"));
+        assert!(filter.allows("Спасибо, а сколько стоит доставка?"));
+        assert!(filter.allows("Нужна консультация"));
+
+        assert!(Prefilter::new(&PrefilterConfig::default()).unwrap().is_none());
+        let bad = PrefilterConfig { min_length: 0, stop_patterns: vec!["(".to_string()] };
+        assert!(Prefilter::new(&bad).is_err());
+    }
+}
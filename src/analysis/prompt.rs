@@ -0,0 +1,369 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::config::GeminiConfig;
+use crate::telegram::RawComment;
+use super::intent::{categories, Intent};
+use super::types::Classification;
+
+/// Default `gemini.prompt_intro`: prompt text before the category list
+pub const DEFAULT_PROMPT_INTRO: &str = r#"You are a B2B lead identification system. You analyze comments in Russian real estate developer Telegram channels to find BUSINESS OWNERS, entrepreneurs, marketers, and executives who could benefit from a "smart Telegram monitoring" service — a tool that automatically scans Telegram channels, finds leads, and analyzes audience activity.
+
+The service helps businesses: find clients in Telegram, monitor competitors, track brand mentions, automate lead generation from public channels.
+
+IMPORTANT: Regular apartment buyers, tenants, and individuals are NOT leads. You are looking for people who represent a business or have a business problem that Telegram monitoring could solve."#;
+
+/// Default `gemini.lead_criteria`: who is a lead and how lead_score is rated
+pub const DEFAULT_LEAD_CRITERIA: &str = r#"Lead identification — be STRICT. is_lead=true ONLY when:
+1. Person is clearly a business owner, marketer, agency owner, or entrepreneur (not an individual)
+2. OR person expresses a pain point that Telegram monitoring directly solves
+3. OR the intent is a category marked (always a lead)
+
+is_lead=false for:
+- Individuals buying/renting for personal use
+- Residents complaining about their apartment
+- General questions about infrastructure, prices for personal purchase
+- Neutral reactions, jokes, emojis
+
+lead_score: 0.0-1.0 reflecting fit for the Telegram monitoring service:
+- 0.8-1.0: Business owner or marketer explicitly discussing lead generation, client acquisition, competitor monitoring, or automation in Telegram
+- 0.5-0.7: Realtor/agency or entrepreneur who likely needs client acquisition tools, or a business that is hiring
+- 0.3-0.5: Investor at scale or person with a pain signal around finding clients/monitoring
+- 0.0-0.2: Individual, not a business lead"#;
+
+/// Prompt text after the lead criteria: the remaining fields and the answer format
+const PROMPT_FORMAT: &str = r#"sentiment: -1.0 to 1.0, the emotional tone of the comment:
+- -1.0 to -0.5: Frustrated, angry, desperate ("опять сорвали сроки, сколько можно")
+- -0.5 to -0.1: Mildly negative, annoyed or worried
+- around 0.0: Neutral, factual, curious
+- 0.1 to 1.0: Positive, satisfied, enthusiastic
+Rate the tone, not whether the person is a lead: a frustrated pain signal is negative.
+
+need_summary: One sentence in Russian describing the person's business role and potential need (empty string if not a lead)
+
+If the original post is given, use it to understand short replies ("yes, exactly what I need"), but classify the commenter, not the post.
+
+Respond ONLY with JSON:
+{"intent": "<category>", "confidence": <0.0-1.0>, "is_lead": <true/false>, "lead_score": <0.0-1.0>, "sentiment": <-1.0-1.0>, "need_summary": "<string>"}"#;
+
+/// Appended to the system prompt when several comments go in one request
+const BATCH_INSTRUCTIONS: &str = r#"You will get several numbered comments. Classify each one independently, as described above.
+Respond ONLY with a JSON object holding one result per comment, in the same order:
+{"results": [{"intent": ..., "confidence": ..., "is_lead": ..., "lead_score": ..., "sentiment": ..., "need_summary": ...}, ...]}"#;
+
+/// Retries on HTTP 429 before giving up
+const MAX_RETRIES: u32 = 4;
+/// Retries on 5xx, timeouts and connection errors before giving up
+const MAX_TRANSIENT_RETRIES: u32 = 2;
+/// Slack past the client timeout before an attempt is abandoned from outside
+const SEND_TIMEOUT_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    results: Vec<IntentResponse>,
+}
+
+#[derive(Deserialize)]
+struct IntentResponse {
+    intent: String,
+    confidence: f32,
+    is_lead: bool,
+    lead_score: f32,
+    /// Older prompts and some models omit it
+    #[serde(default)]
+    sentiment: f32,
+    need_summary: String,
+}
+
+/// Instructions plus the configured categories, shared by all comments.
+/// `intro` and `lead_criteria` come from `gemini.prompt_intro` and
+/// `gemini.lead_criteria`.
+pub fn system_prompt(intro: &str, lead_criteria: &str) -> String {
+    let mut prompt = format!(
+        "{}\n\nIntent categories (classify the comment's primary intent):\n",
+        intro.trim_end()
+    );
+    for category in categories().iter() {
+        let marker = if category.always_lead { " (always a lead)" } else { "" };
+        if category.description.is_empty() {
+            prompt.push_str(&format!("- {}{}\n", category.key, marker));
+        } else {
+            prompt.push_str(&format!("- {}{}: {}\n", category.key, marker, category.description));
+        }
+    }
+    prompt.push('\n');
+    prompt.push_str(lead_criteria.trim_end());
+    prompt.push_str("\n\n");
+    prompt.push_str(PROMPT_FORMAT);
+    prompt
+}
+
+/// The per-comment part of the prompt (everything after `system_prompt`):
+/// optional parent post context, the comment itself, and thread activity.
+pub fn comment_prompt(comment: &RawComment, max_post_context_chars: usize) -> String {
+    let mut prompt = String::new();
+    let post_text = comment.post_text.trim();
+    if max_post_context_chars > 0 && !post_text.is_empty() {
+        let post_context = match post_text.char_indices().nth(max_post_context_chars) {
+            Some((byte_idx, _)) => format!("{}…", &post_text[..byte_idx]),
+            None => post_text.to_string(),
+        };
+        prompt.push_str(&format!(
+            "Original post in channel @{} (the comment replies to it):\n\"{}\"\n\n",
+            comment.channel, post_context
+        ));
+    }
+    if comment.aggregated_comment_ids.len() > 1 {
+        prompt.push_str(&format!(
+            "{} comments from @{} in channel @{}, oldest first; classify the author on all of them together:\n\"{}\"",
+            comment.aggregated_comment_ids.len(),
+            comment.author,
+            comment.channel,
+            comment.text
+        ));
+    } else {
+        prompt.push_str(&format!(
+            "Comment from @{} in channel @{}:\n\"{}\"",
+            comment.author, comment.channel, comment.text
+        ));
+    }
+    let mut flags = Vec::new();
+    if comment.is_premium {
+        flags.push("Telegram Premium");
+    }
+    if comment.is_verified {
+        flags.push("verified account");
+    }
+    if !flags.is_empty() {
+        prompt.push_str(&format!("\n\nAuthor: {} (often a business account).", flags.join(", ")));
+    }
+    if let Some(bio) = comment.author_bio.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        prompt.push_str(&format!("\n\nAuthor's profile bio: \"{}\"", bio));
+    }
+    if comment.comment_velocity > 0 {
+        prompt.push_str(&format!(
+            "\n\nThread activity: {} new comments on this post since the last check.",
+            comment.comment_velocity
+        ));
+    }
+    if let Some(views) = comment.views {
+        prompt.push_str(&format!(
+            "\n\nReach (weak signal): {} views, {} forwards.",
+            views,
+            comment.forwards.unwrap_or(0)
+        ));
+    }
+    prompt
+}
+
+/// The per-comment prompts of a batch, numbered, after the batch instructions.
+pub fn batch_prompt(comments: &[RawComment], max_post_context_chars: usize) -> String {
+    let mut prompt = BATCH_INSTRUCTIONS.to_string();
+    for (i, comment) in comments.iter().enumerate() {
+        prompt.push_str(&format!(
+            "\n\n### Comment {}\n{}",
+            i + 1,
+            comment_prompt(comment, max_post_context_chars)
+        ));
+    }
+    prompt
+}
+
+/// Parses the model's JSON answer (see the format at the end of `PROMPT_FORMAT`).
+pub fn parse_classification(text: &str) -> Result<Classification> {
+    let parsed: IntentResponse =
+        serde_json::from_str(extract_json(text)).context("Failed to parse intent JSON from model")?;
+    Ok(classification_from(parsed))
+}
+
+/// Parses a batch answer (see `BATCH_INSTRUCTIONS`), in comment order.
+pub fn parse_batch(text: &str) -> Result<Vec<Classification>> {
+    let parsed: BatchResponse =
+        serde_json::from_str(extract_json(text)).context("Failed to parse batch JSON from model")?;
+    Ok(parsed.results.into_iter().map(classification_from).collect())
+}
+
+/// The first balanced `{...}` object in a model answer, which is sometimes
+/// wrapped in ```json fences or followed by an explanation. Returns the text
+/// unchanged if there is no complete object, so the parse error shows it.
+fn extract_json(text: &str) -> &str {
+    let Some(start) = text.find('{') else {
+        return text;
+    };
+
+    let mut depth = 0u32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, ch) in text[start..].char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let json = &text[start..start + i + 1];
+                    if json.len() != text.trim().len() {
+                        debug!("Extracted JSON from a wrapped model answer: {:?}", text);
+                    }
+                    return json;
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+fn classification_from(parsed: IntentResponse) -> Classification {
+    let intent = Intent::from_model(&parsed.intent);
+    let is_lead = parsed.is_lead || intent.always_lead();
+
+    Classification {
+        intent,
+        confidence: parsed.confidence,
+        is_lead,
+        lead_score: parsed.lead_score,
+        sentiment: parsed.sentiment.clamp(-1.0, 1.0),
+        need_summary: parsed.need_summary,
+    }
+}
+
+/// HTTP client for a backend, with the request and connect timeouts from `config`.
+pub fn http_client(config: &GeminiConfig) -> Result<Client> {
+    Client::builder()
+        .timeout(request_timeout(config))
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs.max(1)))
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// `request_timeout_secs` as a duration (at least a second).
+pub fn request_timeout(config: &GeminiConfig) -> Duration {
+    Duration::from_secs(config.request_timeout_secs.max(1))
+}
+
+/// Sends the request built by `make`, backing off exponentially on 429 and,
+/// with a shorter budget, on 5xx, timeouts and connection errors.
+/// Fails on any other non-success status, including the response body.
+///
+/// Each attempt is also abandoned (and retried) a little after `timeout`, in
+/// case the client's own timeout doesn't fire on a hung connection.
+pub async fn send_with_retry(
+    provider: &str,
+    timeout: Duration,
+    make: impl Fn() -> RequestBuilder,
+) -> Result<Response> {
+    let mut rate_limited = 0u32;
+    let mut transient = 0u32;
+    loop {
+        let Ok(sent) = tokio::time::timeout(timeout + SEND_TIMEOUT_GRACE, make().send()).await else {
+            transient_backoff(provider, &mut transient, "request timed out".to_string()).await?;
+            continue;
+        };
+        // None = rate limited, Some(reason) = transient failure
+        let failure = match sent {
+            Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                let _ = resp.text().await; // drain body
+                None
+            }
+            Ok(resp) if resp.status().is_server_error() => {
+                let status = resp.status();
+                let _ = resp.text().await;
+                Some(status.to_string())
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                if !status.is_success() {
+                    let body = resp.text().await.unwrap_or_default();
+                    anyhow::bail!("{} API returned {}: {}", provider, status, body);
+                }
+                return Ok(resp);
+            }
+            // URLs may carry API keys; keep them out of logs and /api/selftest
+            Err(e) if e.is_timeout() || e.is_connect() => Some(e.without_url().to_string()),
+            Err(e) => {
+                return Err(e.without_url()).with_context(|| format!("{} API request failed", provider));
+            }
+        };
+
+        match failure {
+            None => backoff(provider, &mut rate_limited, MAX_RETRIES, 5, "429".to_string()).await?,
+            Some(reason) => transient_backoff(provider, &mut transient, reason).await?,
+        }
+    }
+}
+
+async fn transient_backoff(provider: &str, attempt: &mut u32, reason: String) -> Result<()> {
+    backoff(provider, attempt, MAX_TRANSIENT_RETRIES, 2, reason).await
+}
+
+/// Sleeps `base_secs * 2^attempt` before the next retry, or fails once `max`
+/// retries are used up.
+async fn backoff(provider: &str, attempt: &mut u32, max: u32, base_secs: u64, reason: String) -> Result<()> {
+    if *attempt >= max {
+        anyhow::bail!("{} API failed after {} retries: {}", provider, max, reason);
+    }
+    let wait_secs = base_secs * 2u64.pow(*attempt);
+    warn!("{} API {}, retry {}/{} in {}s", provider, reason, *attempt + 1, max, wait_secs);
+    tokio::time::sleep(Duration::from_secs(wait_secs)).await;
+    *attempt += 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_json_from_fenced_answer() {
+        let text = "```json\n{\"need_summary\": \"a } in \\\"text\\\"\", \"n\": {\"x\": 1}}\n```\nHope this helps!";
+        assert_eq!(
+            extract_json(text),
+            "{\"need_summary\": \"a } in \\\"text\\\"\", \"n\": {\"x\": 1}}"
+        );
+        assert_eq!(extract_json("{\"a\": 1}"), "{\"a\": 1}");
+        assert_eq!(extract_json("no json here"), "no json here");
+    }
+
+    #[test]
+    fn parses_batch_results_in_order() {
+        let text = r#"{"results": [
+            {"intent": "spam", "confidence": 0.9, "is_lead": false, "lead_score": 0.0, "need_summary": ""},
+            {"intent": "hiring", "confidence": 0.8, "is_lead": false, "lead_score": 0.6, "sentiment": -1.7, "need_summary": "Ищет менеджера"}
+        ]}"#;
+
+        let results = parse_batch(text).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].intent.key(), "spam");
+        assert_eq!(results[1].intent.key(), "hiring");
+        // Built-in hiring is always a lead, whatever the model says
+        assert!(results[1].is_lead);
+        assert!(!results[0].is_lead);
+        assert_eq!(results[0].sentiment, 0.0);
+        assert_eq!(results[1].sentiment, -1.0);
+    }
+
+    #[test]
+    fn always_lead_comes_from_the_category_config() {
+        let category: crate::analysis::intent::Category = toml::from_str(
+            "key = \"hiring\"\nlabel = \"Hiring\"\ncss_class = \"intent-hiring\"",
+        )
+        .unwrap();
+        assert!(!category.always_lead);
+        let prompt = system_prompt(DEFAULT_PROMPT_INTRO, DEFAULT_LEAD_CRITERIA);
+        assert!(prompt.contains("- hiring (always a lead): "));
+        assert!(!prompt.contains("- spam (always a lead)"));
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{error, warn};
+
+use crate::storage::write_atomic;
+use super::intent::Intent;
+use super::types::Classification;
+
+/// Texts shorter than this usually only make sense next to their post ("yes",
+/// "how much?"), so the same words can mean different things; don't cache them.
+const MIN_CACHED_CHARS: usize = 20;
+/// Rewrite text_cache.json after this many new entries
+const SAVE_EVERY: usize = 25;
+
+/// LRU cache of classifications keyed by a hash of the normalized comment text,
+/// so copy-pasted spam and promo messages are classified once.
+///
+/// `need_summary` is not cached: it describes the author, not the text, so hits
+/// come back with an empty summary.
+pub struct TextCache {
+    path: PathBuf,
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<u64, (u64, CachedClassification)>,
+    /// Use tick → key, oldest first
+    order: BTreeMap<u64, u64>,
+    tick: u64,
+    unsaved: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedClassification {
+    intent: Intent,
+    confidence: f32,
+    is_lead: bool,
+    lead_score: f32,
+    #[serde(default)]
+    sentiment: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    hash: u64,
+    #[serde(flatten)]
+    classification: CachedClassification,
+}
+
+impl TextCache {
+    /// Opens `text_cache.json` in `data_dir`, starting empty if it is missing or unreadable.
+    pub fn open(data_dir: PathBuf, capacity: usize) -> Self {
+        let path = data_dir.join("text_cache.json");
+        let mut inner = Inner::default();
+
+        if path.exists() {
+            let loaded = std::fs::read_to_string(&path)
+                .context("Failed to read text_cache.json")
+                .and_then(|text| {
+                    serde_json::from_str::<Vec<Entry>>(&text).context("Failed to parse text_cache.json")
+                });
+            match loaded {
+                // Saved oldest first, so replaying inserts restores the LRU order
+                Ok(entries) => {
+                    for e in entries {
+                        inner.insert(e.hash, e.classification, capacity);
+                    }
+                    inner.unsaved = 0;
+                }
+                Err(e) => warn!("Ignoring text cache {}: {:#}", path.display(), e),
+            }
+        }
+
+        Self {
+            path,
+            capacity,
+            inner: Mutex::new(inner),
+        }
+    }
+
+    pub fn get(&self, text: &str) -> Option<Classification> {
+        let hash = text_hash(text)?;
+        let mut inner = self.inner.lock().unwrap();
+        let cached = inner.touch(hash)?;
+        Some(Classification {
+            intent: cached.intent,
+            confidence: cached.confidence,
+            is_lead: cached.is_lead,
+            lead_score: cached.lead_score,
+            sentiment: cached.sentiment,
+            need_summary: String::new(),
+        })
+    }
+
+    /// Stores a fresh classification, persisting the cache every `SAVE_EVERY` inserts.
+    pub async fn insert(&self, text: &str, classification: &Classification) {
+        let Some(hash) = text_hash(text) else {
+            return;
+        };
+        let cached = CachedClassification {
+            intent: classification.intent.clone(),
+            confidence: classification.confidence,
+            is_lead: classification.is_lead,
+            lead_score: classification.lead_score,
+            sentiment: classification.sentiment,
+        };
+
+        let snapshot = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.insert(hash, cached, self.capacity);
+            if inner.unsaved < SAVE_EVERY {
+                return;
+            }
+            inner.unsaved = 0;
+            inner.snapshot()
+        };
+
+        if let Err(e) = self.save(&snapshot).await {
+            error!("Failed to save text cache: {:#}", e);
+        }
+    }
+
+    async fn save(&self, entries: &[Entry]) -> Result<()> {
+        let json = serde_json::to_string(entries).context("Failed to serialize text cache")?;
+        write_atomic(&self.path, json.as_bytes()).await
+    }
+}
+
+impl Inner {
+    fn touch(&mut self, hash: u64) -> Option<CachedClassification> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (used, cached) = self.entries.get_mut(&hash)?;
+        self.order.remove(used);
+        self.order.insert(tick, hash);
+        *used = tick;
+        Some(cached.clone())
+    }
+
+    fn insert(&mut self, hash: u64, cached: CachedClassification, capacity: usize) {
+        self.tick += 1;
+        if let Some((used, _)) = self.entries.insert(hash, (self.tick, cached)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, hash);
+        while self.entries.len() > capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.unsaved += 1;
+    }
+
+    /// Entries oldest first.
+    fn snapshot(&self) -> Vec<Entry> {
+        self.order
+            .values()
+            .map(|hash| Entry {
+                hash: *hash,
+                classification: self.entries[hash].1.clone(),
+            })
+            .collect()
+    }
+}
+
+/// FNV-1a of the lowercased, whitespace-collapsed text (stable across builds,
+/// unlike `DefaultHasher`). None for texts too short to cache.
+fn text_hash(text: &str) -> Option<u64> {
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if normalized.chars().count() < MIN_CACHED_CHARS {
+        return None;
+    }
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in normalized.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_ignores_case_and_spacing() {
+        let a = text_hash("Продаю  базу клиентов\nнедорого, пишите в ЛС");
+        let b = text_hash("продаю базу клиентов недорого, пишите в лс ");
+        assert!(a.is_some());
+        assert_eq!(a, b);
+        assert_eq!(text_hash("да, интересно"), None);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cached = CachedClassification {
+            intent: Intent::from_model("spam"),
+            confidence: 0.9,
+            is_lead: false,
+            lead_score: 0.0,
+            sentiment: 0.0,
+        };
+        let mut inner = Inner::default();
+        inner.insert(1, cached.clone(), 2);
+        inner.insert(2, cached.clone(), 2);
+        inner.touch(1);
+        inner.insert(3, cached, 2);
+
+        assert!(inner.entries.contains_key(&1));
+        assert!(!inner.entries.contains_key(&2));
+        assert!(inner.entries.contains_key(&3));
+    }
+}
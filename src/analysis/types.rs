@@ -1,16 +1,49 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::telegram::channel_ref::ChannelRef;
+use crate::telegram::RawComment;
 use super::intent::Intent;
 
+/// What a model decided about a comment, before it's joined with the comment.
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub intent: Intent,
+    pub confidence: f32,
+    pub is_lead: bool,
+    pub lead_score: f32,
+    pub sentiment: f32,
+    pub need_summary: String,
+}
+
+impl Classification {
+    /// Used when analysis fails: a neutral non-lead.
+    pub fn neutral() -> Self {
+        Self {
+            intent: Intent::fallback(),
+            confidence: 0.0,
+            is_lead: false,
+            lead_score: 0.0,
+            sentiment: 0.0,
+            need_summary: String::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalyzedComment {
     pub channel: String,
     pub post_id: i32,
     pub comment_id: i32,
+    /// Telegram user ID of the author (0 if anonymous)
+    #[serde(default)]
+    pub author_id: i64,
     pub author: String,
     pub username: Option<String>,
     pub phone: Option<String>,
+    /// `phone` in E.164 form, when it could be normalized
+    #[serde(default)]
+    pub phone_e164: Option<String>,
     pub text: String,
     pub date: DateTime<Utc>,
     pub intent: Intent,
@@ -19,7 +52,224 @@ pub struct AnalyzedComment {
     pub is_lead: bool,
     /// 0.0-1.0, how likely this person needs help/has a problem
     pub lead_score: f32,
+    /// -1.0 (frustrated) to 1.0 (enthusiastic); 0.0 when unknown
+    #[serde(default)]
+    pub sentiment: f32,
     /// Short summary of what the person needs (empty if not a lead)
     pub need_summary: String,
     pub analyzed_at: DateTime<Utc>,
+    /// New comments on the parent post in the poll this comment arrived in
+    #[serde(default)]
+    pub comment_velocity: u32,
+    /// (emoji, count) per reaction when the comment was fetched
+    #[serde(default)]
+    pub reactions: Vec<(String, i32)>,
+    /// Comment this one replies to (None for a direct reply to the post)
+    #[serde(default)]
+    pub reply_to_comment_id: Option<i32>,
+    #[serde(default)]
+    pub is_premium: bool,
+    #[serde(default)]
+    pub is_verified: bool,
+    /// Forum topic the comment was posted in (then `post_id` is the topic's ID)
+    #[serde(default)]
+    pub topic_id: Option<i32>,
+    /// Re-analyzed after an edit; replaces the earlier record of the same comment
+    #[serde(default)]
+    pub edited: bool,
+    /// As fetched (see `RawComment::views`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub views: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwards: Option<i32>,
+    /// Lead marked as contacted on the dashboard
+    #[serde(default)]
+    pub contacted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contacted_at: Option<DateTime<Utc>>,
+    /// Text of the channel post the comment is under, for the lead page
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub post_text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_bio: Option<String>,
+    /// Comments classified together with this one (see `gemini.author_aggregation_secs`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregated_comment_ids: Vec<i32>,
+}
+
+impl AnalyzedComment {
+    /// Joins a comment with its classification, stamped with the current time.
+    pub fn new(comment: &RawComment, classification: Classification) -> Self {
+        Self {
+            channel: comment.channel.clone(),
+            post_id: comment.post_id,
+            comment_id: comment.comment_id,
+            author_id: comment.author_id,
+            author: comment.author.clone(),
+            username: comment.username.clone(),
+            phone: comment.phone.clone(),
+            phone_e164: comment.phone_e164.clone(),
+            text: comment.text.clone(),
+            date: comment.date,
+            intent: classification.intent,
+            confidence: classification.confidence,
+            is_lead: classification.is_lead,
+            lead_score: classification.lead_score,
+            sentiment: classification.sentiment,
+            need_summary: classification.need_summary,
+            analyzed_at: Utc::now(),
+            comment_velocity: comment.comment_velocity,
+            reactions: comment.reactions.clone(),
+            reply_to_comment_id: comment.reply_to_comment_id,
+            is_premium: comment.is_premium,
+            is_verified: comment.is_verified,
+            topic_id: comment.topic_id,
+            edited: comment.edited,
+            views: comment.views,
+            forwards: comment.forwards,
+            contacted: false,
+            contacted_at: None,
+            post_text: comment.post_text.clone(),
+            author_bio: comment.author_bio.clone(),
+            aggregated_comment_ids: comment.aggregated_comment_ids.clone(),
+        }
+    }
+
+    /// This comment's classification, for reuse on another comment.
+    pub fn classification(&self) -> Classification {
+        Classification {
+            intent: self.intent.clone(),
+            confidence: self.confidence,
+            is_lead: self.is_lead,
+            lead_score: self.lead_score,
+            sentiment: self.sentiment,
+            need_summary: self.need_summary.clone(),
+        }
+    }
+
+    /// Stable author identity: Telegram ID when known, otherwise lowercased username.
+    /// Anonymous comments have none.
+    pub fn author_key(&self) -> Option<String> {
+        if self.author_id != 0 {
+            Some(self.author_id.to_string())
+        } else {
+            self.username.as_ref().map(|u| u.to_lowercase())
+        }
+    }
+
+    /// The person behind a lead across channels, for de-duplicated counts: the
+    /// lowercased username, or the display name when there is none.
+    pub fn person_key(&self) -> String {
+        match &self.username {
+            Some(u) => u.trim_start_matches('@').to_lowercase(),
+            None => self.author.clone(),
+        }
+    }
+
+    /// The phone in E.164 form when it could be normalized, else as captured.
+    pub fn phone_display(&self) -> Option<&str> {
+        self.phone_e164.as_deref().or(self.phone.as_deref())
+    }
+
+    /// A phone was captured but couldn't be normalized to E.164.
+    pub fn phone_unverified(&self) -> bool {
+        self.phone.is_some() && self.phone_e164.is_none()
+    }
+
+    /// Whether `other` is the same Telegram comment (e.g. an edited version of it).
+    pub fn same_comment(&self, other: &AnalyzedComment) -> bool {
+        self.comment_id == other.comment_id && self.channel == other.channel
+    }
+
+    /// Identity of a unique lead: the author, or the comment itself if anonymous.
+    pub fn lead_key(&self) -> String {
+        self.author_key().unwrap_or_else(|| {
+            format!("{}/{}/{}", self.channel, self.post_id, self.comment_id)
+        })
+    }
+
+    /// `https://t.me/<username>`, or `https://t.me/c/<id>` for a channel configured
+    /// by ID. None for one joined by invite link, whose ID we don't keep.
+    fn channel_link(&self) -> Option<String> {
+        match ChannelRef::parse(&self.channel) {
+            ChannelRef::Username(name) => Some(format!("https://t.me/{}", name)),
+            ChannelRef::Id(id) => Some(format!("https://t.me/c/{}", id)),
+            ChannelRef::Invite(_) => None,
+        }
+    }
+
+    /// Link to the channel post this comment is under.
+    pub fn post_url(&self) -> Option<String> {
+        Some(format!("{}/{}", self.channel_link()?, self.post_id))
+    }
+
+    /// Link to this comment in its channel's discussion thread, or in its forum topic.
+    pub fn comment_url(&self) -> Option<String> {
+        let channel = self.channel_link()?;
+        Some(match self.topic_id {
+            Some(topic_id) => format!("{}/{}/{}", channel, topic_id, self.comment_id),
+            None => format!("{}/{}?comment={}", channel, self.post_id, self.comment_id),
+        })
+    }
+
+    /// Reactions as display text, e.g. "👍 3 ❤️ 1" (empty if none).
+    pub fn reactions_display(&self) -> String {
+        self.reactions
+            .iter()
+            .map(|(emoji, count)| format!("{} {}", emoji, count))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Text truncated to at most `max_chars` characters (0 = no limit), with an
+    /// ellipsis appended when cut. Returns the preview and whether it was truncated.
+    pub fn text_preview(&self, max_chars: usize) -> (String, bool) {
+        if max_chars == 0 {
+            return (self.text.clone(), false);
+        }
+        match self.text.char_indices().nth(max_chars) {
+            Some((byte_idx, _)) => (format!("{}…", self.text[..byte_idx].trim_end()), true),
+            None => (self.text.clone(), false),
+        }
+    }
+}
+
+/// Comment factories shared by tests across modules.
+#[cfg(test)]
+pub mod fixtures {
+    use super::*;
+
+    /// A plain comment in @pik under post 10, by an anonymous author; tests set
+    /// the fields they care about.
+    pub fn raw_comment(comment_id: i32) -> RawComment {
+        RawComment {
+            channel: "pik".to_string(),
+            post_id: 10,
+            comment_id,
+            author_id: 0,
+            author: "Автор".to_string(),
+            username: None,
+            phone: None,
+            phone_e164: None,
+            text: String::new(),
+            post_text: String::new(),
+            date: Utc::now(),
+            reactions: Vec::new(),
+            comment_velocity: 0,
+            reply_to_comment_id: None,
+            author_bio: None,
+            is_premium: false,
+            is_verified: false,
+            topic_id: None,
+            edited: false,
+            views: None,
+            forwards: None,
+            aggregated_comment_ids: Vec::new(),
+        }
+    }
+
+    /// `raw_comment` analyzed as a neutral non-lead.
+    pub fn analyzed_comment(comment_id: i32) -> AnalyzedComment {
+        AnalyzedComment::new(&raw_comment(comment_id), Classification::neutral())
+    }
 }
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::config::GeminiConfig;
+
+/// Running token totals across all backend requests since startup.
+pub struct UsageMeter {
+    requests: AtomicU64,
+    prompt_tokens: AtomicU64,
+    output_tokens: AtomicU64,
+    /// USD per 1K prompt tokens
+    input_cost_per_1k: f64,
+    /// USD per 1K output tokens
+    output_cost_per_1k: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageSnapshot {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+    /// At the configured per-1K rates (0 if none are set)
+    pub estimated_cost_usd: f64,
+    /// Classifications below `gemini.min_confidence`
+    pub low_confidence: u64,
+}
+
+impl UsageMeter {
+    pub fn new(config: &GeminiConfig) -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            prompt_tokens: AtomicU64::new(0),
+            output_tokens: AtomicU64::new(0),
+            input_cost_per_1k: config.input_cost_per_1k,
+            output_cost_per_1k: config.output_cost_per_1k,
+        }
+    }
+
+    /// Counts one answered request.
+    pub fn record(&self, prompt_tokens: u64, output_tokens: u64) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.prompt_tokens.fetch_add(prompt_tokens, Ordering::Relaxed);
+        self.output_tokens.fetch_add(output_tokens, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let prompt_tokens = self.prompt_tokens.load(Ordering::Relaxed);
+        let output_tokens = self.output_tokens.load(Ordering::Relaxed);
+        UsageSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            prompt_tokens,
+            output_tokens,
+            total_tokens: prompt_tokens + output_tokens,
+            estimated_cost_usd: prompt_tokens as f64 / 1000.0 * self.input_cost_per_1k
+                + output_tokens as f64 / 1000.0 * self.output_cost_per_1k,
+            low_confidence: 0,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Ring buffer of recent ERROR-level log events, served at `/api/errors`.
+///
+/// Filled by a tracing layer, so every `error!` site is captured without
+/// having to push to it explicitly.
+#[derive(Clone)]
+pub struct ErrorLog {
+    inner: Arc<Mutex<ErrorLogInner>>,
+}
+
+struct ErrorLogInner {
+    entries: VecDeque<ErrorEntry>,
+    capacity: usize,
+    total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorEntry {
+    pub at: DateTime<Utc>,
+    /// Top-level module the error came from (telegram, analysis, storage, ...)
+    pub component: String,
+    pub target: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+impl ErrorLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ErrorLogInner {
+                entries: VecDeque::with_capacity(capacity),
+                capacity: capacity.max(1),
+                total: 0,
+            })),
+        }
+    }
+
+    /// Resizes the buffer once config is loaded (logging starts before that).
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.lock();
+        inner.capacity = capacity.max(1);
+        while inner.entries.len() > inner.capacity {
+            inner.entries.pop_front();
+        }
+    }
+
+    /// Recent errors, newest first.
+    pub fn recent(&self) -> Vec<ErrorEntry> {
+        self.lock().entries.iter().rev().cloned().collect()
+    }
+
+    /// Errors logged since startup (including ones evicted from the buffer).
+    pub fn total(&self) -> u64 {
+        self.lock().total
+    }
+
+    pub fn layer(&self) -> ErrorLayer {
+        ErrorLayer { log: self.clone() }
+    }
+
+    fn push(&self, entry: ErrorEntry) {
+        let mut inner = self.lock();
+        if inner.entries.len() >= inner.capacity {
+            inner.entries.pop_front();
+        }
+        inner.entries.push_back(entry);
+        inner.total += 1;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ErrorLogInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub struct ErrorLayer {
+    log: ErrorLog,
+}
+
+impl<S: Subscriber> Layer<S> for ErrorLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() != Level::ERROR {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let target = metadata.target().to_string();
+        let component = target
+            .split("::")
+            .nth(1)
+            .unwrap_or(&target)
+            .to_string();
+
+        self.log.push(ErrorEntry {
+            at: Utc::now(),
+            component,
+            target,
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+}
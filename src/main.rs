@@ -1,69 +1,223 @@
 mod analysis;
 mod config;
+mod diagnostics;
+mod notify;
 mod storage;
 mod telegram;
 mod web;
 
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use anyhow::Result;
 use tokio::sync::{broadcast, mpsc};
-use tracing::info;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// How long shutdown waits for each stage (scraper, analyzer, storage) to finish
+const SHUTDOWN_STAGE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Delay before the first scraper restart; doubles per failure up to the max
+const SCRAPER_RESTART_BACKOFF: Duration = Duration::from_secs(5);
+const SCRAPER_RESTART_BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// Consecutive failed runs before the scraper is given up on
+const SCRAPER_MAX_FAILURES: u32 = 10;
+/// A run lasting this long counts as healthy and resets the failure count
+const SCRAPER_HEALTHY_RUN: Duration = Duration::from_secs(600);
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
+    let error_log = diagnostics::ErrorLog::new(100);
+    // ATENTO_LOG_FORMAT=json: one JSON object per line, with the fields of the
+    // current span (channel, post_id, comment_id) and its parents
+    let json_logs = std::env::var("ATENTO_LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json"));
+    tracing_subscriber::registry()
+        .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "atento=info".into()),
         )
+        .with((!json_logs).then(tracing_subscriber::fmt::layer))
+        .with(json_logs.then(|| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+        }))
+        .with(error_log.layer())
         .init();
 
     info!("Loading configuration...");
     let config = config::AppConfig::load()?;
+    error_log.set_capacity(config.web.error_buffer_size);
 
     // Channels
     let (raw_tx, raw_rx) = mpsc::channel::<telegram::RawComment>(256);
     let (analyzed_tx, _) = broadcast::channel::<analysis::AnalyzedComment>(256);
 
+    // Per-author history (optional)
+    let author_history = if config.storage.author_history {
+        std::fs::create_dir_all(&config.storage.data_dir)?;
+        Some(storage::AuthorHistory::load(&config.storage)?)
+    } else {
+        None
+    };
+
+    // Analyzer (Gemini, an OpenAI-compatible backend, or the offline mock)
+    let analyzer = Arc::new(analysis::AnalysisPipeline::new(
+        analysis::build_analyzer(&config.gemini)?,
+        &config.gemini,
+        &config.storage,
+    )?);
+
+    // Cancelled on Ctrl-C / SIGTERM
+    let shutdown = CancellationToken::new();
+
+    // Telegram connection health, reported by the scraper and served at /healthz
+    let telegram_health = telegram::TelegramHealth::default();
+
+    // Contacted marks: dashboard → storage (for leads.json)
+    let (lead_contact_tx, lead_contact_rx) = mpsc::channel::<storage::LeadContact>(64);
+
+    // Kafka delivery counters, served at /metrics/kafka while the sink runs
+    let kafka_metrics = (cfg!(feature = "kafka") && config.kafka.is_some())
+        .then(|| Arc::new(storage::KafkaMetrics::default()));
+
     // App state for web
-    let app_state = web::state::AppState::new(analyzed_tx.clone(), config.web.recent_buffer_size);
+    let app_state = web::state::AppState::new(
+        &config.web,
+        author_history,
+        analyzer.clone(),
+        telegram_health.clone(),
+        error_log,
+        lead_contact_tx,
+        shutdown.clone(),
+    )
+    .with_max_leads(config.storage.max_leads)
+    .with_kafka(kafka_metrics.clone());
+
+    // Lifetime stats: seed the dashboard and storage writer from stats.json
+    let lifetime_stats = storage::LifetimeStats::load(&config.storage.data_dir)?;
+    *app_state.stats.write().await = web::state::Stats::from(&lifetime_stats);
 
     // Channel status: scraper → storage (for channels.json)
     let (channel_status_tx, channel_status_rx) = mpsc::channel::<(String, bool)>(64);
 
-    // Storage writer
-    let storage_writer = storage::StorageWriter::new(&config.storage, channel_status_rx);
+    // Storage writer (no lead webhook in backfill mode, see `run_backfill`)
+    let outreach = notify::OutreachTemplates::new(config.outreach.clone());
+    let webhook = if config.mode == config::Mode::Backfill {
+        None
+    } else {
+        notify::Webhook::from_config(&config.notify)?
+    };
+    let storage_writer = storage::StorageWriter::new(
+        &config.storage,
+        channel_status_rx,
+        lead_contact_rx,
+        lifetime_stats,
+        outreach.clone(),
+        webhook,
+    );
     let storage_rx = analyzed_tx.subscribe();
 
+    if config.mode == config::Mode::Backfill {
+        return run_backfill(&config, analyzer, analyzed_tx, storage_writer, storage_rx, channel_status_tx).await;
+    }
+    // Restored leads stay on the dashboard (and can be marked contacted) after a restart
+    app_state.restore_leads(storage_writer.leads().to_vec()).await;
+    *app_state.channel_stats.write().await = storage_writer.channel_stats().clone();
+
+    // Channel status: also to the dashboard (for /api/channels); the storage side
+    // closes when the scraper stops, as before
+    let (scraper_status_tx, mut scraper_status_rx) = mpsc::channel::<(String, bool)>(64);
+    let state_for_status = app_state.clone();
+    tokio::spawn(async move {
+        while let Some((channel, has_comments)) = scraper_status_rx.recv().await {
+            state_for_status.set_channel_status(channel.clone(), has_comments).await;
+            if channel_status_tx.send((channel, has_comments)).await.is_err() {
+                break;
+            }
+        }
+    });
+
     // Web state updater
     let state_for_updater = app_state.clone();
     let mut updater_rx = analyzed_tx.subscribe();
 
-    // Gemini analyzer
-    let analyzer = Arc::new(analysis::GeminiAnalyzer::new(&config.gemini));
 
-    // Telegram scraper
-    let scraper = telegram::TelegramScraper::connect(&config.telegram, channel_status_tx).await?;
+    // Lead notifier
+    let notifier = notify::Notifier::new(&config.notify, config.web.preview_chars, outreach)?
+        .with_bot(notify::TelegramBot::spawn(&config.notify)?);
+    let notifier_rx = analyzed_tx.subscribe();
 
-    // Spawn tasks
-    let scraper_handle = tokio::spawn(async move {
-        if let Err(e) = scraper.run(raw_tx).await {
-            tracing::error!("Telegram scraper error: {:#}", e);
+    // Kafka sink (optional)
+    let kafka_handle: Option<tokio::task::JoinHandle<()>> = match &config.kafka {
+        #[cfg(feature = "kafka")]
+        Some(kafka_config) => {
+            let sink = storage::KafkaSink::new(kafka_config, kafka_metrics.unwrap_or_default())?;
+            let kafka_rx = analyzed_tx.subscribe();
+            Some(tokio::spawn(async move {
+                if let Err(e) = sink.run(kafka_rx).await {
+                    tracing::error!("Kafka sink error: {:#}", e);
+                }
+            }))
         }
-    });
+        #[cfg(not(feature = "kafka"))]
+        Some(_) => {
+            tracing::warn!("[kafka] is configured but atento was built without the `kafka` feature");
+            None
+        }
+        None => None,
+    };
+
+    // Notion sink (optional)
+    let notion_handle = match &config.notion {
+        Some(notion_config) => {
+            let sink = notify::NotionSink::new(notion_config, &config.storage.data_dir)?;
+            let notion_rx = analyzed_tx.subscribe();
+            Some(tokio::spawn(async move {
+                if let Err(e) = sink.run(notion_rx).await {
+                    tracing::error!("Notion sink error: {:#}", e);
+                }
+            }))
+        }
+        None => None,
+    };
 
-    let analyzer_handle = tokio::spawn(async move {
+    // Telegram scraper (a failed connect or sign-in fails startup; later runs
+    // are restarted on the same session)
+    let scraper = telegram::TelegramScraper::connect(
+        &config.telegram,
+        scraper_status_tx,
+        telegram_health.clone(),
+    )
+    .await?;
+
+    // Spawn tasks
+    let mut scraper_handle = tokio::spawn(supervise_scraper(
+        scraper,
+        raw_tx,
+        telegram_health,
+        shutdown.clone(),
+    ));
+
+    let mut analyzer_handle = tokio::spawn(async move {
         if let Err(e) = analyzer.run(raw_rx, analyzed_tx).await {
-            tracing::error!("Gemini analyzer error: {:#}", e);
+            tracing::error!("Analyzer error: {:#}", e);
         }
     });
 
-    let storage_handle = tokio::spawn(async move {
+    let mut storage_handle = tokio::spawn(async move {
         if let Err(e) = storage_writer.run(storage_rx).await {
             tracing::error!("Storage writer error: {:#}", e);
         }
     });
 
+    let notifier_handle = tokio::spawn(async move {
+        if let Err(e) = notifier.run(notifier_rx).await {
+            tracing::error!("Notifier error: {:#}", e);
+        }
+    });
+
     // State updater: keeps AppState in sync with broadcast
     let updater_handle = tokio::spawn(async move {
         loop {
@@ -85,20 +239,187 @@ async fn main() -> Result<()> {
     info!("Starting web server at http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let web_shutdown = shutdown.clone();
     let web_handle = tokio::spawn(async move {
-        if let Err(e) = axum::serve(listener, router).await {
+        let serve = axum::serve(listener, router).with_graceful_shutdown(web_shutdown.cancelled_owned());
+        if let Err(e) = serve.await {
             tracing::error!("Web server error: {:#}", e);
         }
     });
 
-    // Wait for any task to finish (shouldn't under normal operation)
+    // Wait for a shutdown signal or any task to finish (shouldn't under normal operation)
     tokio::select! {
-        _ = scraper_handle => info!("Scraper task ended"),
-        _ = analyzer_handle => info!("Analyzer task ended"),
-        _ = storage_handle => info!("Storage task ended"),
+        _ = shutdown_signal() => info!("Shutdown requested"),
+        _ = &mut scraper_handle => info!("Scraper task ended"),
+        _ = &mut analyzer_handle => info!("Analyzer task ended"),
+        _ = &mut storage_handle => info!("Storage task ended"),
         _ = updater_handle => info!("Updater task ended"),
+        _ = notifier_handle => info!("Notifier task ended"),
+        _ = async {
+            match kafka_handle {
+                Some(handle) => drop(handle.await),
+                None => std::future::pending().await,
+            }
+        } => info!("Kafka sink ended"),
+        _ = async {
+            match notion_handle {
+                Some(handle) => drop(handle.await),
+                None => std::future::pending().await,
+            }
+        } => info!("Notion sink ended"),
         _ = web_handle => info!("Web server ended"),
     }
 
+    // Stop at the source and let the pipeline drain: the scraper exits and drops
+    // the raw channel, the analyzer finishes in-flight work and drops the broadcast
+    // sender, and storage writes its reports one last time as it closes.
+    shutdown.cancel();
+    for (name, handle) in [
+        ("scraper", &mut scraper_handle),
+        ("analyzer", &mut analyzer_handle),
+        ("storage writer", &mut storage_handle),
+    ] {
+        if handle.is_finished() {
+            continue;
+        }
+        if tokio::time::timeout(SHUTDOWN_STAGE_TIMEOUT, &mut *handle).await.is_err() {
+            warn!("Timed out waiting for the {} to stop, aborting it", name);
+            handle.abort();
+        }
+    }
+    info!("Shutdown complete");
+
     Ok(())
 }
+
+/// `mode = "backfill"`: fetches the last `backfill_days` of every channel once and
+/// returns when all of it is analyzed and stored. No web server or notifications,
+/// so a month of history doesn't page anyone. Ctrl-C stops fetching but still
+/// finishes what was already fetched.
+async fn run_backfill(
+    config: &config::AppConfig,
+    analyzer: Arc<analysis::AnalysisPipeline>,
+    analyzed_tx: broadcast::Sender<analysis::AnalyzedComment>,
+    storage_writer: storage::StorageWriter,
+    storage_rx: broadcast::Receiver<analysis::AnalyzedComment>,
+    channel_status_tx: mpsc::Sender<(String, bool)>,
+) -> Result<()> {
+    let since = chrono::Utc::now() - chrono::Duration::days(config.telegram.backfill_days.into());
+    info!("Backfill mode: analyzing comments of the last {} days", config.telegram.backfill_days);
+
+    let scraper = telegram::TelegramScraper::connect(
+        &config.telegram,
+        channel_status_tx,
+        telegram::TelegramHealth::default(),
+    )
+    .await?;
+
+    let (raw_tx, raw_rx) = mpsc::channel::<telegram::RawComment>(256);
+    let analyzer_handle = tokio::spawn(analyzer.run(raw_rx, analyzed_tx));
+    let storage_handle = tokio::spawn(storage_writer.run(storage_rx));
+
+    tokio::select! {
+        result = scraper.backfill(raw_tx, since) => {
+            if let Err(e) = result {
+                tracing::error!("Backfill error: {:#}", e);
+            }
+        }
+        _ = shutdown_signal() => info!("Shutdown requested, finishing the comments already fetched"),
+    }
+
+    // The scraper's sender is gone: the analyzer drains and closes the broadcast,
+    // then storage writes its reports one last time
+    analyzer_handle.await??;
+    storage_handle.await??;
+    info!("Backfill complete");
+    Ok(())
+}
+
+/// Runs the scraper until shutdown, restarting it with exponential backoff when
+/// a run ends early (an error or a panic). Restarts reuse the same scraper, so
+/// its session and the comments it has seen carry over; a lost sign-in is not
+/// retried, as that needs an interactive login. Gives up after
+/// `SCRAPER_MAX_FAILURES` failures in a row.
+async fn supervise_scraper(
+    scraper: telegram::TelegramScraper,
+    raw_tx: mpsc::Sender<telegram::RawComment>,
+    health: telegram::TelegramHealth,
+    shutdown: CancellationToken,
+) {
+    // Each run is its own task so a panic is caught here; the scraper outlives it
+    // behind a tokio mutex, which the panic doesn't poison
+    let scraper = Arc::new(tokio::sync::Mutex::new(scraper));
+    let mut failures = 0u32;
+    let mut backoff = SCRAPER_RESTART_BACKOFF;
+
+    loop {
+        let started = Instant::now();
+        let run = {
+            let (scraper, raw_tx, shutdown) = (scraper.clone(), raw_tx.clone(), shutdown.clone());
+            tokio::spawn(async move { scraper.lock().await.run(raw_tx, shutdown).await })
+        };
+        let result = run.await;
+        if shutdown.is_cancelled() {
+            return;
+        }
+        match result {
+            Ok(Ok(())) => warn!("Telegram scraper stopped unexpectedly"),
+            Ok(Err(e)) => tracing::error!("Telegram scraper error: {:#}", e),
+            Err(e) => tracing::error!("Telegram scraper panicked: {}", e),
+        }
+        if !health.snapshot().authorized {
+            tracing::error!("Telegram session is no longer authorized, not restarting the scraper");
+            return;
+        }
+        if started.elapsed() >= SCRAPER_HEALTHY_RUN {
+            failures = 0;
+            backoff = SCRAPER_RESTART_BACKOFF;
+        }
+
+        failures += 1;
+        if failures > SCRAPER_MAX_FAILURES {
+            tracing::error!("Telegram scraper failed {} times in a row, giving up", SCRAPER_MAX_FAILURES);
+            return;
+        }
+        warn!(
+            "Restarting Telegram scraper in {}s (attempt {}/{})",
+            backoff.as_secs(),
+            failures,
+            SCRAPER_MAX_FAILURES
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = shutdown.cancelled() => return,
+        }
+        backoff = (backoff * 2).min(SCRAPER_RESTART_BACKOFF_MAX);
+    }
+}
+
+/// Resolves on Ctrl-C or (on Unix) SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => drop(sigterm.recv().await),
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,144 @@
+pub mod notion;
+pub mod outreach;
+pub mod rule;
+pub mod telegram_bot;
+pub mod webhook;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::analysis::AnalyzedComment;
+use crate::config::NotifyConfig;
+pub use notion::NotionSink;
+pub use outreach::OutreachTemplates;
+pub use rule::LeadRule;
+pub use telegram_bot::TelegramBot;
+pub use webhook::Webhook;
+
+/// Telegram only allows edits this long after posting, so notified comments
+/// older than this can't come back
+const EDIT_WINDOW: chrono::Duration = chrono::Duration::hours(48);
+
+/// Decides which leads fire notifications. Without a rule every lead does.
+pub struct Notifier {
+    rule: Option<LeadRule>,
+    preview_chars: usize,
+    outreach: OutreachTemplates,
+    /// Bot alerts for leads that pass the rule (None = off)
+    bot: Option<TelegramBot>,
+    /// (channel, comment_id) → date of notified comments still open to edits,
+    /// so an edited lead isn't announced again
+    notified: HashMap<(String, i32), DateTime<Utc>>,
+}
+
+impl Notifier {
+    pub fn new(
+        config: &NotifyConfig,
+        preview_chars: usize,
+        outreach: OutreachTemplates,
+    ) -> Result<Self> {
+        let rule = config.rule.as_deref().map(LeadRule::parse).transpose()?;
+        Ok(Self {
+            rule,
+            preview_chars,
+            outreach,
+            bot: None,
+            notified: HashMap::new(),
+        })
+    }
+
+    pub fn should_notify(&self, comment: &AnalyzedComment) -> bool {
+        comment.is_lead && self.rule.as_ref().is_none_or(|r| r.matches(comment))
+    }
+
+    /// Also sends notified leads to a Telegram chat.
+    pub fn with_bot(mut self, bot: Option<TelegramBot>) -> Self {
+        self.bot = bot;
+        self
+    }
+
+    /// Whether `comment` should be announced now: it passes the rule and, if it
+    /// is an edit, wasn't announced before the edit. Remembers it if so.
+    fn first_notification(&mut self, comment: &AnalyzedComment) -> bool {
+        if !self.should_notify(comment) {
+            return false;
+        }
+        let cutoff = Utc::now() - EDIT_WINDOW;
+        self.notified.retain(|_, date| *date > cutoff);
+        let key = (comment.channel.clone(), comment.comment_id);
+        if comment.edited && self.notified.contains_key(&key) {
+            return false;
+        }
+        if comment.date > cutoff {
+            self.notified.insert(key, comment.date);
+        }
+        true
+    }
+
+    pub async fn run(mut self, mut rx: broadcast::Receiver<AnalyzedComment>) -> Result<()> {
+        info!("Notifier started (rule: {:?})", self.rule);
+
+        loop {
+            match rx.recv().await {
+                Ok(comment) => {
+                    if self.first_notification(&comment) {
+                        let (preview, _) = comment.text_preview(self.preview_chars);
+                        info!(
+                            "NOTIFY @{} [{}] {} ({:.0}%): {} — \"{}\" {}",
+                            comment.channel,
+                            comment.intent.key(),
+                            comment.author,
+                            comment.lead_score * 100.0,
+                            comment.need_summary,
+                            preview,
+                            comment.comment_url().unwrap_or_default()
+                        );
+                        if let Some(draft) = self.outreach.render(&comment) {
+                            info!("NOTIFY draft: {}", draft);
+                        }
+                        if let Some(bot) = &self.bot {
+                            bot.notify(&comment);
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Notifier lagged, skipped {} messages", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::analyzed_comment;
+
+    fn comment(edited: bool, is_lead: bool) -> AnalyzedComment {
+        let mut c = analyzed_comment(20);
+        c.author_id = 1;
+        c.edited = edited;
+        c.is_lead = is_lead;
+        c
+    }
+
+    #[test]
+    fn edited_lead_is_announced_once() {
+        let new = || Notifier::new(&NotifyConfig::default(), 0, OutreachTemplates::new(HashMap::new())).unwrap();
+
+        let mut notifier = new();
+        assert!(notifier.first_notification(&comment(false, true)));
+        assert!(!notifier.first_notification(&comment(true, true)));
+
+        // An edit that turns the comment into a lead is news
+        let mut notifier = new();
+        assert!(!notifier.first_notification(&comment(false, false)));
+        assert!(notifier.first_notification(&comment(true, true)));
+    }
+}
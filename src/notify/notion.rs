@@ -0,0 +1,302 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+use crate::analysis::AnalyzedComment;
+use crate::config::NotionConfig;
+use crate::storage::{append_jsonl, write_atomic};
+
+const NOTION_PAGES_URL: &str = "https://api.notion.com/v1/pages";
+const NOTION_VERSION: &str = "2022-06-28";
+/// Notion allows ~3 requests/s per integration
+const REQUEST_SPACING: Duration = Duration::from_millis(350);
+const MAX_RETRIES: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Comments remembered as written; the oldest are forgotten first
+const MAX_SENT: usize = 50_000;
+
+/// Creates one page per new lead comment in a Notion database. Comments are
+/// deduplicated by `channel/comment_id`, so a re-analyzed (edited) comment
+/// doesn't get a second page.
+///
+/// Expected database properties: "Name" (title, author), "Link" (url),
+/// "Channel" (rich text), "Intent" (select), "Score" (number),
+/// "Summary" (rich text), "Status" (select) and "Comment ID" (rich text,
+/// `channel/comment_id` of the comment the page was created for).
+pub struct NotionSink {
+    client: Client,
+    token: String,
+    database_id: String,
+    status: String,
+    flush_interval: Duration,
+    sent: SentLog,
+    pending: Vec<AnalyzedComment>,
+}
+
+/// Comments already written, persisted to notion_sent.jsonl so restarts don't
+/// duplicate pages. Holds the last `capacity` keys; the file is rewritten with
+/// just those once it has twice as many lines.
+struct SentLog {
+    path: PathBuf,
+    capacity: usize,
+    keys: HashSet<String>,
+    /// `keys` in the order they were written, oldest first
+    order: VecDeque<String>,
+    /// Lines in the file, including forgotten and duplicate keys
+    lines: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SentRecord {
+    key: String,
+}
+
+/// A page that Notion didn't reject
+enum PageOutcome {
+    Created,
+    /// Transport error, 429 or 5xx still failing after `MAX_RETRIES`
+    Unavailable(String),
+}
+
+impl NotionSink {
+    pub fn new(config: &NotionConfig, data_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(data_dir).context("Failed to create data directory")?;
+        let sent = SentLog::load(data_dir.join("notion_sent.jsonl"), MAX_SENT)?;
+
+        Ok(Self {
+            client: Client::new(),
+            token: config.token.clone(),
+            database_id: config.database_id.clone(),
+            status: config.status.clone(),
+            flush_interval: Duration::from_secs(config.flush_interval_secs.max(1)),
+            sent,
+            pending: Vec::new(),
+        })
+    }
+
+    pub async fn run(mut self, mut rx: broadcast::Receiver<AnalyzedComment>) -> Result<()> {
+        info!("Notion sink started ({} lead comments already written)", self.sent.len());
+        let mut flush = tokio::time::interval(self.flush_interval);
+
+        loop {
+            tokio::select! {
+                result = rx.recv() => match result {
+                    Ok(comment) => {
+                        let key = comment_key(&comment);
+                        if comment.is_lead
+                            && !self.sent.contains(&key)
+                            && !self.pending.iter().any(|c| comment_key(c) == key)
+                        {
+                            self.pending.push(comment);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Notion sink lagged, skipped {} messages", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = flush.tick() => self.flush().await,
+            }
+        }
+
+        self.flush().await;
+        Ok(())
+    }
+
+    /// Writes pending leads one page at a time, spaced to stay under the rate limit.
+    /// If Notion is unavailable, the rest stay pending for the next flush; pages
+    /// Notion rejects are dropped.
+    async fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let mut batch = std::mem::take(&mut self.pending).into_iter();
+        let total = batch.len();
+        let mut written = 0;
+        while let Some(comment) = batch.next() {
+            match self.create_page(&comment).await {
+                Ok(PageOutcome::Created) => {
+                    self.sent.record(comment_key(&comment)).await;
+                    written += 1;
+                }
+                Ok(PageOutcome::Unavailable(reason)) => {
+                    self.pending.push(comment);
+                    self.pending.extend(batch);
+                    warn!("Notion unavailable ({}), keeping {} leads for the next flush", reason, self.pending.len());
+                    break;
+                }
+                Err(e) => error!("Failed to create Notion page for {}: {:#}", comment_key(&comment), e),
+            }
+            tokio::time::sleep(REQUEST_SPACING).await;
+        }
+        info!("Notion sink: wrote {}/{} leads", written, total);
+    }
+
+    async fn create_page(&self, comment: &AnalyzedComment) -> Result<PageOutcome> {
+        let body = json!({
+            "parent": { "database_id": self.database_id },
+            "properties": {
+                "Name": { "title": [{ "text": { "content": comment.author } }] },
+                "Link": { "url": comment.comment_url() },
+                "Channel": { "rich_text": [{ "text": { "content": format!("@{}", comment.channel) } }] },
+                "Intent": { "select": { "name": comment.intent.key() } },
+                "Score": { "number": comment.lead_score },
+                "Summary": { "rich_text": [{ "text": { "content": comment.need_summary } }] },
+                "Status": { "select": { "name": self.status } },
+                "Comment ID": { "rich_text": [{ "text": { "content": comment_key(comment) } }] },
+            }
+        });
+
+        let mut attempt = 0u32;
+        loop {
+            let request = self
+                .client
+                .post(NOTION_PAGES_URL)
+                .bearer_auth(&self.token)
+                .header("Notion-Version", NOTION_VERSION)
+                .timeout(REQUEST_TIMEOUT)
+                .json(&body);
+
+            let backoff = Duration::from_secs(1 << attempt);
+            let (retryable, wait) = match request.send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(PageOutcome::Created),
+                Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    let wait_secs = resp
+                        .headers()
+                        .get("retry-after")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(1u64);
+                    (format!("status {}", resp.status()), Duration::from_secs(wait_secs))
+                }
+                Ok(resp) if resp.status().is_server_error() => (format!("status {}", resp.status()), backoff),
+                Ok(resp) => {
+                    let status = resp.status();
+                    let body = resp.text().await.unwrap_or_default();
+                    anyhow::bail!("Notion API returned {}: {}", status, body);
+                }
+                Err(e) => (format!("{:#}", anyhow::Error::from(e)), backoff),
+            };
+
+            if attempt >= MAX_RETRIES {
+                return Ok(PageOutcome::Unavailable(retryable));
+            }
+            warn!("Notion {}, retry {}/{} in {:?}", retryable, attempt + 1, MAX_RETRIES, wait);
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
+
+impl SentLog {
+    fn load(path: PathBuf, capacity: usize) -> Result<Self> {
+        let mut log = Self {
+            path,
+            capacity: capacity.max(1),
+            keys: HashSet::new(),
+            order: VecDeque::new(),
+            lines: 0,
+        };
+        if !log.path.exists() {
+            return Ok(log);
+        }
+        let text = std::fs::read_to_string(&log.path)
+            .with_context(|| format!("Failed to read {}", log.path.display()))?;
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            log.lines += 1;
+            match serde_json::from_str::<SentRecord>(line) {
+                Ok(r) => log.remember(r.key),
+                Err(e) => warn!("Skipping bad line in {}: {}", log.path.display(), e),
+            }
+        }
+        Ok(log)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Records a written page, compacting the file once it has grown to twice
+    /// the keys kept.
+    async fn record(&mut self, key: String) {
+        self.remember(key.clone());
+        if self.lines + 1 >= self.capacity * 2 {
+            if let Err(e) = self.compact().await {
+                error!("Failed to compact {}: {:#}", self.path.display(), e);
+            }
+            return;
+        }
+        match append_jsonl(&self.path, &SentRecord { key }).await {
+            Ok(()) => self.lines += 1,
+            Err(e) => error!("Failed to record Notion page: {:#}", e),
+        }
+    }
+
+    fn remember(&mut self, key: String) {
+        if !self.keys.insert(key.clone()) {
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+    }
+
+    /// Rewrites the file with only the keys kept.
+    async fn compact(&mut self) -> Result<()> {
+        let mut lines = String::new();
+        for key in &self.order {
+            let record = SentRecord { key: key.clone() };
+            lines.push_str(&serde_json::to_string(&record).context("Failed to serialize Notion record")?);
+            lines.push('\n');
+        }
+        write_atomic(&self.path, lines.as_bytes()).await?;
+        self.lines = self.order.len();
+        Ok(())
+    }
+}
+
+/// `channel/comment_id`, the dedup key and the page's "Comment ID"
+fn comment_key(comment: &AnalyzedComment) -> String {
+    format!("{}/{}", comment.channel, comment.comment_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sent_log_keeps_the_latest_keys_and_compacts() {
+        let dir = std::env::temp_dir().join(format!("atento-notion-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notion_sent.jsonl");
+
+        let mut log = SentLog::load(path.clone(), 3).unwrap();
+        for id in 1..=6 {
+            log.record(format!("pik/{}", id)).await;
+        }
+        assert!(!log.contains("pik/3"));
+        assert!(log.contains("pik/4") && log.contains("pik/6"));
+
+        // The sixth write compacted the file down to the three keys kept
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+        let reloaded = SentLog::load(path, 3).unwrap();
+        assert_eq!(reloaded.len(), 3);
+        assert!(reloaded.contains("pik/6"));
+        assert!(!reloaded.contains("pik/1"));
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+
+use crate::analysis::{AnalyzedComment, Intent};
+
+/// Per-intent first-contact drafts with `{author}`, `{username}`, `{channel}`
+/// and `{need_summary}` placeholders.
+#[derive(Debug, Clone, Default)]
+pub struct OutreachTemplates {
+    templates: HashMap<Intent, String>,
+}
+
+impl OutreachTemplates {
+    pub fn new(templates: HashMap<Intent, String>) -> Self {
+        Self { templates }
+    }
+
+    /// Fills the template for the comment's intent, if one is configured.
+    pub fn render(&self, comment: &AnalyzedComment) -> Option<String> {
+        let template = self.templates.get(&comment.intent)?;
+        let username = comment
+            .username
+            .as_deref()
+            .map(|u| format!("@{}", u))
+            .unwrap_or_default();
+        Some(
+            template
+                .replace("{author}", &comment.author)
+                .replace("{username}", &username)
+                .replace("{channel}", comment.channel.trim_start_matches('@'))
+                .replace("{need_summary}", &comment.need_summary),
+        )
+    }
+}
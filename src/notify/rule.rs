@@ -0,0 +1,365 @@
+use anyhow::{bail, Context, Result};
+
+use crate::analysis::{AnalyzedComment, Intent};
+
+/// A boolean filter over analyzed comments, parsed from a small expression language:
+///
+/// ```text
+/// score >= 0.6 AND (intent in [marketer, business_owner] OR text contains "лиды")
+/// ```
+///
+/// Fields: `score` (lead_score), `confidence`, `velocity`, `intent`, `channel`,
+/// `text`, `is_lead`. `NOT` binds tighter than `AND`, which binds tighter than `OR`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeadRule {
+    And(Box<LeadRule>, Box<LeadRule>),
+    Or(Box<LeadRule>, Box<LeadRule>),
+    Not(Box<LeadRule>),
+    Number(NumField, CmpOp, f64),
+    IntentIn(Vec<Intent>),
+    ChannelIn(Vec<String>),
+    TextContains(String),
+    IsLead,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumField {
+    Score,
+    Confidence,
+    Velocity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Eq,
+    Ne,
+}
+
+impl LeadRule {
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let rule = parser.or_expr()?;
+        if let Some(tok) = parser.peek() {
+            bail!("Unexpected `{}` in rule", tok);
+        }
+        Ok(rule)
+    }
+
+    pub fn matches(&self, c: &AnalyzedComment) -> bool {
+        match self {
+            LeadRule::And(a, b) => a.matches(c) && b.matches(c),
+            LeadRule::Or(a, b) => a.matches(c) || b.matches(c),
+            LeadRule::Not(r) => !r.matches(c),
+            LeadRule::Number(field, op, value) => {
+                let actual = match field {
+                    NumField::Score => c.lead_score as f64,
+                    NumField::Confidence => c.confidence as f64,
+                    NumField::Velocity => c.comment_velocity as f64,
+                };
+                match op {
+                    CmpOp::Ge => actual >= *value,
+                    CmpOp::Gt => actual > *value,
+                    CmpOp::Le => actual <= *value,
+                    CmpOp::Lt => actual < *value,
+                    CmpOp::Eq => (actual - value).abs() < f64::EPSILON,
+                    CmpOp::Ne => (actual - value).abs() >= f64::EPSILON,
+                }
+            }
+            LeadRule::IntentIn(intents) => intents.contains(&c.intent),
+            LeadRule::ChannelIn(channels) => {
+                let channel = c.channel.trim_start_matches('@');
+                channels.iter().any(|ch| ch.eq_ignore_ascii_case(channel))
+            }
+            LeadRule::TextContains(needle) => c.text.to_lowercase().contains(needle),
+            LeadRule::IsLead => c.is_lead,
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '[' | ']' | ',' => {
+                tokens.push(ch.to_string());
+                chars.next();
+            }
+            '>' | '<' | '=' | '!' => {
+                chars.next();
+                let mut op = ch.to_string();
+                if chars.peek() == Some(&'=') {
+                    op.push('=');
+                    chars.next();
+                }
+                tokens.push(op);
+            }
+            '"' => {
+                chars.next();
+                let mut s = String::from("\"");
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => s.push(c),
+                        None => bail!("Unterminated string in rule"),
+                    }
+                }
+                tokens.push(s);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || matches!(c, '_' | '.' | '@' | '-') {
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if word.is_empty() {
+                    bail!("Unexpected character `{}` in rule", ch);
+                }
+                tokens.push(word);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|s| s.as_str())
+    }
+
+    fn next(&mut self) -> Result<String> {
+        let tok = self.tokens.get(self.pos).cloned().context("Unexpected end of rule")?;
+        self.pos += 1;
+        Ok(tok)
+    }
+
+    fn eat_keyword(&mut self, kw: &str) -> bool {
+        if self.peek().is_some_and(|t| t.eq_ignore_ascii_case(kw)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        let tok = self.next()?;
+        if !tok.eq_ignore_ascii_case(expected) {
+            bail!("Expected `{}` in rule, found `{}`", expected, tok);
+        }
+        Ok(())
+    }
+
+    fn or_expr(&mut self) -> Result<LeadRule> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("OR") {
+            let right = self.and_expr()?;
+            left = LeadRule::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<LeadRule> {
+        let mut left = self.unary()?;
+        while self.eat_keyword("AND") {
+            let right = self.unary()?;
+            left = LeadRule::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<LeadRule> {
+        if self.eat_keyword("NOT") {
+            return Ok(LeadRule::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some("(") {
+            self.pos += 1;
+            let inner = self.or_expr()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<LeadRule> {
+        let field = self.next()?.to_lowercase();
+        match field.as_str() {
+            "score" | "lead_score" | "confidence" | "velocity" => {
+                let num_field = match field.as_str() {
+                    "confidence" => NumField::Confidence,
+                    "velocity" => NumField::Velocity,
+                    _ => NumField::Score,
+                };
+                let op = match self.next()?.as_str() {
+                    ">=" => CmpOp::Ge,
+                    ">" => CmpOp::Gt,
+                    "<=" => CmpOp::Le,
+                    "<" => CmpOp::Lt,
+                    "=" | "==" => CmpOp::Eq,
+                    "!=" => CmpOp::Ne,
+                    other => bail!("Unknown comparison `{}` for `{}`", other, field),
+                };
+                let value_tok = self.next()?;
+                let value: f64 = value_tok
+                    .parse()
+                    .with_context(|| format!("`{}` is not a number", value_tok))?;
+                Ok(LeadRule::Number(num_field, op, value))
+            }
+            "intent" => {
+                let values = self.in_or_eq_list()?;
+                let intents = values
+                    .iter()
+                    .map(|v| Intent::known(v).with_context(|| format!("Unknown intent `{}` in rule", v)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(LeadRule::IntentIn(intents))
+            }
+            "channel" => {
+                let channels = self
+                    .in_or_eq_list()?
+                    .into_iter()
+                    .map(|c| c.trim_start_matches('@').to_string())
+                    .collect();
+                Ok(LeadRule::ChannelIn(channels))
+            }
+            "text" => {
+                self.expect("contains")?;
+                let tok = self.next()?;
+                let needle = tok.strip_prefix('"').unwrap_or(&tok).to_lowercase();
+                Ok(LeadRule::TextContains(needle))
+            }
+            "is_lead" => Ok(LeadRule::IsLead),
+            other => bail!("Unknown field `{}` in rule", other),
+        }
+    }
+
+    /// Parses `in [a, b, ...]` or `== a` into a list of values.
+    fn in_or_eq_list(&mut self) -> Result<Vec<String>> {
+        let op = self.next()?;
+        if op == "=" || op == "==" {
+            return Ok(vec![self.value()?]);
+        }
+        if !op.eq_ignore_ascii_case("in") {
+            bail!("Expected `in` or `==`, found `{}`", op);
+        }
+        self.expect("[")?;
+        let mut values = Vec::new();
+        loop {
+            values.push(self.value()?);
+            match self.next()?.as_str() {
+                "," => continue,
+                "]" => break,
+                other => bail!("Expected `,` or `]` in list, found `{}`", other),
+            }
+        }
+        Ok(values)
+    }
+
+    fn value(&mut self) -> Result<String> {
+        let tok = self.next()?;
+        Ok(tok.strip_prefix('"').map(str::to_string).unwrap_or(tok))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::analyzed_comment;
+
+    fn comment(score: f32, intent: &str, channel: &str, text: &str) -> AnalyzedComment {
+        let mut c = analyzed_comment(1);
+        c.lead_score = score;
+        c.confidence = 0.5;
+        c.comment_velocity = 3;
+        c.intent = Intent::known(intent).unwrap();
+        c.channel = channel.to_string();
+        c.text = text.to_string();
+        c
+    }
+
+    fn rule(input: &str) -> LeadRule {
+        LeadRule::parse(input).unwrap()
+    }
+
+    fn error(input: &str) -> String {
+        format!("{:#}", LeadRule::parse(input).unwrap_err())
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        assert_eq!(rule("is_lead OR is_lead AND NOT is_lead"), rule("is_lead OR (is_lead AND (NOT is_lead))"));
+        assert_ne!(rule("is_lead OR is_lead AND is_lead"), rule("(is_lead OR is_lead) AND is_lead"));
+
+        let c = comment(0.9, "spam", "pik", "");
+        // (score > 0.5) OR (intent == marketer AND NOT is_lead)
+        assert!(rule("score > 0.5 OR intent == marketer AND NOT is_lead").matches(&c));
+        assert!(!rule("(score > 0.5 OR intent == marketer) AND is_lead").matches(&c));
+        assert!(rule("NOT (is_lead OR score < 0.5)").matches(&c));
+        assert!(!rule("not is_lead and score < 0.5").matches(&c));
+    }
+
+    #[test]
+    fn intent_and_channel_take_lists_or_single_values() {
+        let c = comment(0.5, "marketer", "@PIK_Group", "");
+        assert!(rule("intent in [business_owner, Marketer]").matches(&c));
+        assert!(rule("intent == marketer").matches(&c));
+        assert!(!rule("intent in [spam]").matches(&c));
+        assert!(rule("channel in [other, \"pik_group\"]").matches(&c));
+        assert!(rule("channel = @pik_group").matches(&c));
+        assert!(!rule("channel == other").matches(&c));
+    }
+
+    #[test]
+    fn text_contains_matches_quoted_phrases_case_insensitively() {
+        let c = comment(0.5, "neutral", "pik", "Где найти Лиды для агентства?");
+        assert!(rule("text contains \"лиды для\"").matches(&c));
+        assert!(rule("text contains агентства").matches(&c));
+        assert!(!rule("text contains \"лиды  для\"").matches(&c));
+    }
+
+    #[test]
+    fn compares_numeric_fields() {
+        let c = comment(0.75, "neutral", "pik", "");
+        assert!(rule("score >= 0.75").matches(&c));
+        assert!(rule("lead_score <= 0.75").matches(&c));
+        assert!(!rule("score > 0.75").matches(&c));
+        assert!(!rule("score < 0.75").matches(&c));
+        assert!(rule("confidence == 0.5").matches(&c));
+        assert!(rule("velocity != 2").matches(&c));
+        assert!(rule("velocity = 3").matches(&c));
+    }
+
+    #[test]
+    fn malformed_rules_explain_what_is_wrong() {
+        assert_eq!(error("score >= high"), "`high` is not a number: invalid float literal");
+        assert_eq!(error("score ~ 1"), "Unexpected character `~` in rule");
+        assert_eq!(error("score like 1"), "Unknown comparison `like` for `score`");
+        assert_eq!(error("author == ivan"), "Unknown field `author` in rule");
+        assert_eq!(error("intent in [buyer]"), "Unknown intent `buyer` in rule");
+        assert_eq!(error("intent in [spam marketer]"), "Expected `,` or `]` in list, found `marketer`");
+        assert_eq!(error("intent has spam"), "Expected `in` or `==`, found `has`");
+        assert_eq!(error("text has \"x\""), "Expected `contains` in rule, found `has`");
+        assert_eq!(error("text contains \"лиды"), "Unterminated string in rule");
+        assert_eq!(error("(is_lead"), "Unexpected end of rule");
+        assert_eq!(error("is_lead is_lead"), "Unexpected `is_lead` in rule");
+        assert_eq!(error("score >="), "Unexpected end of rule");
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::analysis::AnalyzedComment;
+use crate::config::NotifyConfig;
+
+const BOT_API_URL: &str = "https://api.telegram.org";
+/// Telegram allows ~20 messages/min to one group; stay under it
+const MESSAGE_SPACING: Duration = Duration::from_secs(3);
+/// Alerts waiting to be sent; more are dropped rather than delaying the notifier
+const QUEUE_SIZE: usize = 100;
+const MAX_RETRIES: u32 = 3;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends lead alerts to a chat through the Bot API, one message every
+/// `MESSAGE_SPACING`.
+pub struct TelegramBot {
+    tx: mpsc::Sender<String>,
+    min_score: f32,
+}
+
+struct BotSender {
+    client: Client,
+    token: String,
+    chat_id: String,
+}
+
+#[derive(Deserialize)]
+struct BotResponse {
+    ok: bool,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    parameters: Option<ResponseParameters>,
+}
+
+#[derive(Deserialize)]
+struct ResponseParameters {
+    #[serde(default)]
+    retry_after: Option<u64>,
+}
+
+impl TelegramBot {
+    /// Starts the sender task; None when no `telegram_chat_id` is configured.
+    pub fn spawn(config: &NotifyConfig) -> Result<Option<Self>> {
+        let Some(chat_id) = config.telegram_chat_id.clone() else {
+            return Ok(None);
+        };
+        let client = Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .context("Failed to build Bot API HTTP client")?;
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        let sender = BotSender {
+            client,
+            token: config.telegram_bot_token.clone(),
+            chat_id,
+        };
+        tokio::spawn(sender.run(rx));
+
+        Ok(Some(Self {
+            tx,
+            min_score: config.telegram_min_score,
+        }))
+    }
+
+    /// Queues an alert for `comment` if it scores at least `telegram_min_score`.
+    pub fn notify(&self, comment: &AnalyzedComment) {
+        if comment.lead_score < self.min_score {
+            return;
+        }
+        if self.tx.try_send(format_lead(comment)).is_err() {
+            warn!("Telegram alert queue full, dropping lead {}", comment.lead_key());
+        }
+    }
+}
+
+impl BotSender {
+    async fn run(self, mut rx: mpsc::Receiver<String>) {
+        info!("Telegram bot notifications started (chat {})", self.chat_id);
+        while let Some(text) = rx.recv().await {
+            if let Err(e) = self.send(&text).await {
+                error!("Failed to send Telegram alert: {:#}", e);
+            }
+            tokio::time::sleep(MESSAGE_SPACING).await;
+        }
+    }
+
+    async fn send(&self, text: &str) -> Result<()> {
+        let url = format!("{}/bot{}/sendMessage", BOT_API_URL, self.token);
+        let body = json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "parse_mode": "HTML",
+            "disable_web_page_preview": true,
+        });
+
+        let mut attempt = 0u32;
+        loop {
+            let resp = self
+                .client
+                .post(&url)
+                .json(&body)
+                .send()
+                .await
+                // The URL carries the bot token
+                .map_err(|e| e.without_url())
+                .context("Bot API request failed")?;
+            let status = resp.status();
+            let text = resp
+                .text()
+                .await
+                .map_err(|e| e.without_url())
+                .context("Failed to read Bot API response")?;
+
+            // A proxy in front of the Bot API may answer 5xx with an HTML page
+            let (reason, wait) = match serde_json::from_str::<BotResponse>(&text) {
+                Ok(resp) if resp.ok => return Ok(()),
+                Ok(BotResponse {
+                    parameters: Some(ResponseParameters { retry_after: Some(secs) }),
+                    ..
+                }) => ("rate limit".to_string(), Duration::from_secs(secs)),
+                _ if status.is_server_error() => (format!("status {}", status), Duration::from_secs(1 << attempt)),
+                Ok(resp) => anyhow::bail!("Bot API error: {}", resp.description.unwrap_or_default()),
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to parse Bot API response (status {})", status))
+                }
+            };
+
+            if attempt >= MAX_RETRIES {
+                anyhow::bail!("Bot API {}, giving up after {} retries", reason, MAX_RETRIES);
+            }
+            warn!("Bot API {}, retry {}/{} in {:?}", reason, attempt + 1, MAX_RETRIES, wait);
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// HTML alert: author, @username, channel, need summary and the comment link.
+fn format_lead(c: &AnalyzedComment) -> String {
+    let mut text = format!(
+        "🎯 <b>{}</b> ({:.0}%)\n👤 {}",
+        escape(&c.intent.label()),
+        c.lead_score * 100.0,
+        escape(&c.author)
+    );
+    if let Some(username) = &c.username {
+        text.push_str(&format!(" @{}", escape(username.trim_start_matches('@'))));
+    }
+    text.push_str(&format!("\n📢 @{}", escape(c.channel.trim_start_matches('@'))));
+    if !c.need_summary.is_empty() {
+        text.push_str(&format!("\n💬 {}", escape(&c.need_summary)));
+    }
+    if let Some(url) = c.comment_url() {
+        text.push_str(&format!("\n{}", url));
+    }
+    text
+}
+
+/// Escapes text for the Bot API's HTML parse mode.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+use crate::config::NotifyConfig;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RETRIES: u32 = 2;
+/// `sha256=<hex HMAC-SHA256 of the body>`, keyed by WEBHOOK_SECRET
+pub const SIGNATURE_HEADER: &str = "X-Atento-Signature";
+
+/// POSTs new leads as JSON to `notify.webhook_url`.
+#[derive(Clone)]
+pub struct Webhook {
+    client: Client,
+    url: String,
+    /// Empty = unsigned
+    secret: String,
+}
+
+impl Webhook {
+    /// None when no `webhook_url` is configured.
+    pub fn from_config(config: &NotifyConfig) -> Result<Option<Self>> {
+        let Some(url) = &config.webhook_url else {
+            return Ok(None);
+        };
+        let client = Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+        Ok(Some(Self {
+            client,
+            url: url.clone(),
+            secret: config.webhook_secret.clone(),
+        }))
+    }
+
+    /// Sends `body` in the background, so a slow receiver never holds up the caller.
+    pub fn send(&self, body: Vec<u8>) {
+        let webhook = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = webhook.post(&body).await {
+                error!("Lead webhook failed: {:#}", e);
+            }
+        });
+    }
+
+    async fn post(&self, body: &[u8]) -> Result<()> {
+        let signature = (!self.secret.is_empty()).then(|| sign(&self.secret, body));
+
+        let mut attempt = 0u32;
+        loop {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .body(body.to_vec());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            let retryable = match request.send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    debug!("Lead webhook delivered ({})", resp.status());
+                    return Ok(());
+                }
+                Ok(resp) if resp.status().is_server_error() => format!("status {}", resp.status()),
+                Ok(resp) => anyhow::bail!("Webhook returned {}", resp.status()),
+                Err(e) => format!("{:#}", anyhow::Error::from(e)),
+            };
+
+            if attempt >= MAX_RETRIES {
+                anyhow::bail!("Gave up after {} retries: {}", MAX_RETRIES, retryable);
+            }
+            let wait = Duration::from_secs(1 << attempt);
+            warn!("Lead webhook {}, retry {}/{} in {:?}", retryable, attempt + 1, MAX_RETRIES, wait);
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// `sha256=` + hex HMAC-SHA256 of `body`.
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::analysis::AnalyzedComment;
+use crate::config::StorageConfig;
+use super::writer::append_jsonl;
+
+/// Append-only history of every analyzed comment per author, across all channels.
+///
+/// Persisted to `authors.jsonl` in the data dir and replayed on startup; the
+/// in-memory copy keeps at most `limit` most recent comments per author.
+#[derive(Clone)]
+pub struct AuthorHistory {
+    authors: Arc<RwLock<HashMap<String, VecDeque<AnalyzedComment>>>>,
+    limit: usize,
+    path: PathBuf,
+}
+
+impl AuthorHistory {
+    pub fn load(config: &StorageConfig) -> Result<Self> {
+        let path = config.data_dir.join("authors.jsonl");
+        let limit = config.author_history_limit.max(1);
+        let mut authors: HashMap<String, VecDeque<AnalyzedComment>> = HashMap::new();
+
+        if path.exists() {
+            let text = std::fs::read_to_string(&path).context("Failed to read authors.jsonl")?;
+            for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<AnalyzedComment>(line) {
+                    Ok(comment) => {
+                        if let Some(key) = comment.author_key() {
+                            push_bounded(authors.entry(key).or_default(), comment, limit);
+                        }
+                    }
+                    Err(e) => warn!("Skipping malformed line in authors.jsonl: {}", e),
+                }
+            }
+            info!("Loaded history for {} authors", authors.len());
+        }
+
+        Ok(Self {
+            authors: Arc::new(RwLock::new(authors)),
+            limit,
+            path,
+        })
+    }
+
+    pub async fn record(&self, comment: &AnalyzedComment) -> Result<()> {
+        let Some(key) = comment.author_key() else {
+            return Ok(());
+        };
+
+        append_jsonl(&self.path, comment).await?;
+
+        let mut authors = self.authors.write().await;
+        push_bounded(authors.entry(key).or_default(), comment.clone(), self.limit);
+        Ok(())
+    }
+
+    /// Looks up an author by numeric Telegram ID or username (with or without `@`).
+    pub async fn get(&self, id: &str) -> Option<Vec<AnalyzedComment>> {
+        let key = id.trim_start_matches('@').to_lowercase();
+        let authors = self.authors.read().await;
+        let history = authors.get(&key).or_else(|| {
+            authors.values().find(|h| {
+                h.iter()
+                    .any(|c| c.username.as_deref().is_some_and(|u| u.to_lowercase() == key))
+            })
+        })?;
+        Some(history.iter().cloned().collect())
+    }
+}
+
+/// Adds a comment to an author's history; a re-analyzed edit replaces the original.
+fn push_bounded(history: &mut VecDeque<AnalyzedComment>, comment: AnalyzedComment, limit: usize) {
+    if let Some(existing) = history.iter_mut().find(|c| c.same_comment(&comment)) {
+        *existing = comment;
+        return;
+    }
+    if history.len() >= limit {
+        history.pop_front();
+    }
+    history.push_back(comment);
+}
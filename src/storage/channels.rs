@@ -0,0 +1,162 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::analysis::{AnalyzedComment, Intent};
+use crate::config::StorageConfig;
+use super::stats::{ChannelTotals, LifetimeStats};
+
+/// Per-channel counts behind channels.json. The storage writer and the web state
+/// each keep one, fed from the same broadcast, so /api/channels matches the file.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelStats {
+    stats: HashMap<String, ChannelStat>,
+    min_comments_for_rate: usize,
+    lead_intents: Vec<Intent>,
+    channel_lead_intents: HashMap<String, Vec<Intent>>,
+    pain_signals_report: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ChannelStat {
+    has_comments: Option<bool>,
+    comments_total: usize,
+    leads_total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelEntry {
+    pub name: String,
+    pub has_comments: bool,
+    pub comments_collected: usize,
+    pub leads_found: usize,
+    /// `None` until the channel has at least `min_comments_for_rate` comments
+    pub lead_rate: Option<f64>,
+}
+
+impl ChannelStats {
+    /// Starts from the totals in stats.json, or from the restored `leads` when
+    /// there are none.
+    pub fn new(config: &StorageConfig, lifetime: &LifetimeStats, leads: &[AnalyzedComment]) -> Self {
+        let mut stats: HashMap<String, ChannelStat> = lifetime
+            .by_channel
+            .iter()
+            .map(|(name, totals)| {
+                let stat = ChannelStat {
+                    has_comments: None,
+                    comments_total: totals.comments,
+                    leads_total: totals.leads,
+                };
+                (name.clone(), stat)
+            })
+            .collect();
+        // Without stats.json, the restored leads are the best known counts
+        if lifetime.by_channel.is_empty() {
+            for lead in leads {
+                let stat = stats.entry(lead.channel.clone()).or_default();
+                stat.comments_total += 1;
+                stat.leads_total += 1;
+            }
+        }
+
+        Self {
+            stats,
+            min_comments_for_rate: config.min_comments_for_rate.max(1),
+            lead_intents: config.lead_intents.clone(),
+            channel_lead_intents: config.channel_lead_intents.clone(),
+            pain_signals_report: config.pain_signals_report,
+        }
+    }
+
+    /// Counts a new comment. Returns whether it counts as a lead.
+    pub fn record(&mut self, comment: &AnalyzedComment) -> bool {
+        let is_lead = self.counts_as_lead(comment);
+        let stat = self.stats.entry(comment.channel.clone()).or_default();
+        stat.comments_total += 1;
+        if is_lead {
+            stat.leads_total += 1;
+        }
+        is_lead
+    }
+
+    /// Counts a lead that an edit turned an already counted comment into.
+    pub fn record_new_lead(&mut self, channel: &str) {
+        self.stats.entry(channel.to_string()).or_default().leads_total += 1;
+    }
+
+    pub fn set_has_comments(&mut self, channel: String, has_comments: bool) {
+        self.stats.entry(channel).or_default().has_comments = Some(has_comments);
+    }
+
+    /// A comment counts as a lead for reports if Gemini flagged it and its intent
+    /// is allowed for its channel (per-channel override, else global `lead_intents`).
+    pub fn counts_as_lead(&self, comment: &AnalyzedComment) -> bool {
+        if self.is_pain_signal(comment) {
+            return true;
+        }
+        if !comment.is_lead {
+            return false;
+        }
+        let channel = comment.channel.trim_start_matches('@');
+        let allowed = self
+            .channel_lead_intents
+            .iter()
+            .find(|(name, _)| name.trim_start_matches('@').eq_ignore_ascii_case(channel))
+            .map(|(_, intents)| intents)
+            .unwrap_or(&self.lead_intents);
+        allowed.is_empty() || allowed.contains(&comment.intent)
+    }
+
+    /// With `pain_signals_report` on, every pain_signal comment is a lead regardless
+    /// of Gemini's is_lead and the `lead_intents` filters.
+    pub fn is_pain_signal(&self, comment: &AnalyzedComment) -> bool {
+        self.pain_signals_report && comment.intent.key() == "pain_signal"
+    }
+
+    /// Totals to persist in stats.json.
+    pub fn totals(&self) -> HashMap<String, ChannelTotals> {
+        self.stats
+            .iter()
+            .map(|(name, stat)| {
+                let totals = ChannelTotals {
+                    comments: stat.comments_total,
+                    leads: stat.leads_total,
+                };
+                (name.clone(), totals)
+            })
+            .collect()
+    }
+
+    /// One entry per channel, in channels.json order.
+    pub fn entries(&self) -> Vec<ChannelEntry> {
+        let mut entries: Vec<ChannelEntry> = self
+            .stats
+            .iter()
+            .map(|(name, stat)| {
+                let lead_rate = if stat.comments_total >= self.min_comments_for_rate {
+                    Some(stat.leads_total as f64 / stat.comments_total as f64)
+                } else {
+                    None
+                };
+                ChannelEntry {
+                    name: name.clone(),
+                    has_comments: stat.has_comments.unwrap_or(false),
+                    comments_collected: stat.comments_total,
+                    leads_found: stat.leads_total,
+                    lead_rate,
+                }
+            })
+            .collect();
+
+        // Channels with a rate first (highest rate first), then small samples by volume
+        entries.sort_by(|a, b| match (a.lead_rate, b.lead_rate) {
+            (Some(x), Some(y)) => y
+                .partial_cmp(&x)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.comments_collected.cmp(&a.comments_collected)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => b.comments_collected.cmp(&a.comments_collected),
+        });
+        entries
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::analysis::AnalyzedComment;
+
+/// Canonical machine-readable lead, for CRM import (`GET /api/leads/crm`).
+///
+/// Field names and types are stable; nothing is display-formatted. Absent values
+/// are `null`, never empty strings.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrmLead {
+    /// string — author's display name as shown in Telegram
+    pub full_name: String,
+    /// string | null — first word of `full_name`
+    pub first_name: Option<String>,
+    /// string | null — rest of `full_name` after the first word
+    pub last_name: Option<String>,
+    /// string | null — phone in E.164 (`+79991234567`)
+    pub phone: Option<String>,
+    /// string | null — Telegram username without the leading `@`
+    pub telegram_username: Option<String>,
+    /// integer | null — Telegram user ID
+    pub telegram_user_id: Option<i64>,
+    /// string — source channel username, without `@`
+    pub source_channel: String,
+    /// string | null — permalink to the comment (https://t.me/...); null for
+    /// channels joined by invite link
+    pub comment_url: Option<String>,
+    /// string — intent identifier, e.g. `business_owner` (see `Intent::key`)
+    pub intent: String,
+    /// number — lead score, 0.0 to 1.0
+    pub score: f32,
+    /// string — one-sentence summary of the need (may be empty)
+    pub summary: String,
+    /// string — RFC 3339 timestamp of the comment
+    pub commented_at: DateTime<Utc>,
+}
+
+impl From<&AnalyzedComment> for CrmLead {
+    fn from(c: &AnalyzedComment) -> Self {
+        let full_name = c.author.trim().to_string();
+        let mut parts = full_name.splitn(2, char::is_whitespace);
+        let first_name = parts.next().filter(|s| !s.is_empty()).map(str::to_string);
+        let last_name = parts.next().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+
+        Self {
+            full_name,
+            first_name,
+            last_name,
+            phone: c.phone_e164.clone(),
+            telegram_username: c.username.as_ref().map(|u| u.trim_start_matches('@').to_string()),
+            telegram_user_id: (c.author_id != 0).then_some(c.author_id),
+            source_channel: c.channel.trim_start_matches('@').to_string(),
+            comment_url: c.comment_url(),
+            intent: c.intent.key().to_string(),
+            score: c.lead_score,
+            summary: c.need_summary.clone(),
+            commented_at: c.date,
+        }
+    }
+}
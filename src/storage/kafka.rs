@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+use crate::analysis::AnalyzedComment;
+use crate::config::KafkaConfig;
+use super::KafkaMetrics;
+
+/// Publishes every analyzed comment as JSON to a Kafka topic.
+pub struct KafkaSink {
+    producer: FutureProducer,
+    topic: String,
+    max_retries: u32,
+    metrics: Arc<KafkaMetrics>,
+}
+
+impl KafkaSink {
+    pub fn new(config: &KafkaConfig, metrics: Arc<KafkaMetrics>) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.brokers)
+            .set("message.timeout.ms", "10000");
+        if let Some(protocol) = &config.security_protocol {
+            client_config.set("security.protocol", protocol);
+        }
+        if let Some(mechanism) = &config.sasl_mechanism {
+            client_config.set("sasl.mechanism", mechanism);
+        }
+        if let Some(username) = &config.sasl_username {
+            client_config.set("sasl.username", username);
+        }
+        if !config.sasl_password.is_empty() {
+            client_config.set("sasl.password", &config.sasl_password);
+        }
+
+        let producer: FutureProducer = client_config
+            .create()
+            .context("Failed to create Kafka producer")?;
+
+        Ok(Self {
+            producer,
+            topic: config.topic.clone(),
+            max_retries: config.max_retries,
+            metrics,
+        })
+    }
+
+    pub async fn run(self, mut rx: broadcast::Receiver<AnalyzedComment>) -> Result<()> {
+        info!("Kafka sink started (topic: {})", self.topic);
+
+        loop {
+            match rx.recv().await {
+                Ok(comment) => self.publish(&comment).await,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    let lagged = self.metrics.lagged.fetch_add(n, Ordering::Relaxed) + n;
+                    warn!("Kafka sink lagged, skipped {} messages ({} total)", n, lagged);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+
+        let totals = self.metrics.snapshot();
+        info!(
+            "Kafka sink stopped (sent: {}, errors: {}, lagged: {})",
+            totals.sent, totals.errors, totals.lagged
+        );
+        Ok(())
+    }
+
+    async fn publish(&self, comment: &AnalyzedComment) {
+        let payload = match serde_json::to_string(comment) {
+            Ok(p) => p,
+            Err(e) => {
+                self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+                error!("Failed to serialize comment for Kafka: {}", e);
+                return;
+            }
+        };
+        let key = format!("{}/{}/{}", comment.channel, comment.post_id, comment.comment_id);
+
+        let mut attempt = 0u32;
+        loop {
+            let record = FutureRecord::to(&self.topic).key(&key).payload(&payload);
+            match self.producer.send(record, Duration::from_secs(10)).await {
+                Ok(_) => {
+                    let sent = self.metrics.sent.fetch_add(1, Ordering::Relaxed) + 1;
+                    if sent.is_multiple_of(1000) {
+                        let totals = self.metrics.snapshot();
+                        info!(
+                            "Kafka sink: {} sent, {} errors, {} lagged",
+                            totals.sent, totals.errors, totals.lagged
+                        );
+                    }
+                    return;
+                }
+                Err((e, _)) if attempt < self.max_retries => {
+                    let wait_secs = 2u64.pow(attempt);
+                    warn!(
+                        "Kafka publish failed, retry {}/{} in {}s: {}",
+                        attempt + 1, self.max_retries, wait_secs, e
+                    );
+                    tokio::time::sleep(Duration::from_secs(wait_secs)).await;
+                    attempt += 1;
+                }
+                Err((e, _)) => {
+                    let errors = self.metrics.errors.fetch_add(1, Ordering::Relaxed) + 1;
+                    error!(
+                        "Kafka publish failed after {} retries ({} errors total): {}",
+                        self.max_retries, errors, e
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Delivery counters of the Kafka sink since startup, shared with `/metrics/kafka`.
+#[derive(Default)]
+pub struct KafkaMetrics {
+    pub sent: AtomicU64,
+    /// Comments given up on (serialization failed or retries ran out)
+    pub errors: AtomicU64,
+    /// Comments skipped because the sink fell behind the broadcast channel
+    pub lagged: AtomicU64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KafkaSnapshot {
+    pub sent: u64,
+    pub errors: u64,
+    pub lagged: u64,
+}
+
+impl KafkaMetrics {
+    pub fn snapshot(&self) -> KafkaSnapshot {
+        KafkaSnapshot {
+            sent: self.sent.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            lagged: self.lagged.load(Ordering::Relaxed),
+        }
+    }
+}
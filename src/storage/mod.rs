@@ -1,3 +1,19 @@
+pub mod authors;
+pub mod channels;
+pub mod crm;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod kafka_metrics;
+pub mod retention;
+pub mod stats;
+pub mod vcard;
 pub mod writer;
 
-pub use writer::StorageWriter;
+pub use authors::AuthorHistory;
+pub use channels::ChannelStats;
+pub use crm::CrmLead;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaSink;
+pub use kafka_metrics::KafkaMetrics;
+pub use stats::LifetimeStats;
+pub use writer::{append_jsonl, cap_leads, leads_csv, write_atomic, LeadContact, StorageWriter};
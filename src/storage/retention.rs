@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tracing::info;
+
+/// What happens to old `comments_YYYY-MM-DD.*` files. 0 disables a step.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetentionPolicy {
+    pub compress_after_days: u32,
+    pub retain_days: u32,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.compress_after_days > 0 || self.retain_days > 0
+    }
+}
+
+/// Gzips and deletes daily comment files in `data_dir` by age relative to `today`.
+/// Files dated today or later are never touched.
+pub fn sweep(data_dir: &Path, today: NaiveDate, policy: RetentionPolicy) -> Result<()> {
+    let entries = std::fs::read_dir(data_dir)
+        .with_context(|| format!("Failed to list {}", data_dir.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(date) = comment_file_date(name) else {
+            continue;
+        };
+        if date >= today {
+            continue;
+        }
+        let age_days = (today - date).num_days();
+
+        if policy.retain_days > 0 && age_days >= policy.retain_days as i64 {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            info!("Removed {} ({} days old)", name, age_days);
+        } else if policy.compress_after_days > 0
+            && age_days >= policy.compress_after_days as i64
+            && !name.ends_with(".gz")
+        {
+            compress(&path)?;
+            info!("Compressed {} ({} days old)", name, age_days);
+        }
+    }
+    Ok(())
+}
+
+/// The date in `comments_YYYY-MM-DD.<ext>[.gz]`.
+fn comment_file_date(name: &str) -> Option<NaiveDate> {
+    let rest = name.strip_prefix("comments_")?;
+    let (date, ext) = rest.split_at_checked(10)?;
+    if !ext.starts_with('.') {
+        return None;
+    }
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Writes `<path>.gz` via a temp file, then removes `path`.
+fn compress(path: &Path) -> Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    let mut tmp_path = gz_path.clone();
+    tmp_path.push(".tmp");
+
+    let mut input = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let output = File::create(&tmp_path).context("Failed to create compressed file")?;
+    let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::default());
+    std::io::copy(&mut input, &mut encoder)
+        .with_context(|| format!("Failed to compress {}", path.display()))?;
+    encoder
+        .finish()
+        .and_then(|w| w.into_inner().map_err(|e| e.into_error()))
+        .context("Failed to finish compressed file")?;
+
+    std::fs::rename(&tmp_path, &gz_path).context("Failed to rename compressed file")?;
+    std::fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compresses_then_deletes_by_age_and_spares_today() {
+        let dir = std::env::temp_dir().join(format!("atento_retention_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "comments_2024-03-10.jsonl",
+            "comments_2024-03-05.jsonl",
+            "comments_2024-02-01.jsonl.gz",
+            "comments_2024-02-01.csv",
+            "leads.json",
+        ] {
+            std::fs::write(dir.join(name), "{}\n").unwrap();
+        }
+
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let policy = RetentionPolicy {
+            compress_after_days: 3,
+            retain_days: 30,
+        };
+        sweep(&dir, today, policy).unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            names,
+            vec!["comments_2024-03-05.jsonl.gz", "comments_2024-03-10.jsonl", "leads.json"]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+use crate::analysis::{AnalyzedComment, Intent};
+use super::writer::write_atomic;
+
+/// Cumulative counters persisted to `stats.json` so totals survive restarts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    pub total: usize,
+    pub leads: usize,
+    #[serde(default)]
+    pub by_intent: HashMap<Intent, usize>,
+    #[serde(default)]
+    pub by_channel: HashMap<String, ChannelTotals>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelTotals {
+    pub comments: usize,
+    /// Leads as counted in the leads report (after `lead_intents` filtering)
+    pub leads: usize,
+}
+
+impl LifetimeStats {
+    /// Loads `stats.json` from `data_dir`, or starts from zero if it doesn't exist.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join("stats.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(&path).context("Failed to read stats.json")?;
+        let stats: Self = serde_json::from_str(&text).context("Failed to parse stats.json")?;
+        info!("Loaded lifetime stats: {} comments, {} leads", stats.total, stats.leads);
+        Ok(stats)
+    }
+
+    pub fn record(&mut self, comment: &AnalyzedComment) {
+        self.total += 1;
+        if comment.is_lead {
+            self.leads += 1;
+        }
+        *self.by_intent.entry(comment.intent.clone()).or_insert(0) += 1;
+    }
+
+    pub async fn save(&mut self, data_dir: &Path) -> Result<()> {
+        self.updated_at = Some(Utc::now());
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize stats")?;
+        write_atomic(&data_dir.join("stats.json"), json.as_bytes()).await
+    }
+}
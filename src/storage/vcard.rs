@@ -0,0 +1,106 @@
+use crate::analysis::AnalyzedComment;
+use super::crm::CrmLead;
+
+/// vCard 3.0 contacts for `leads` (`GET /api/leads.vcf`), one per lead that can
+/// be reached: with a phone or a username.
+pub fn leads_vcf<'a>(leads: impl IntoIterator<Item = &'a AnalyzedComment>) -> String {
+    leads
+        .into_iter()
+        .filter(|c| c.phone.is_some() || c.username.is_some())
+        .map(lead_vcard)
+        .collect()
+}
+
+fn lead_vcard(c: &AnalyzedComment) -> String {
+    let lead = CrmLead::from(c);
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:3.0".to_string(),
+        format!("FN:{}", escape(&lead.full_name)),
+        format!(
+            "N:{};{};;;",
+            escape(lead.last_name.as_deref().unwrap_or_default()),
+            escape(lead.first_name.as_deref().unwrap_or_default())
+        ),
+    ];
+    if let Some(phone) = c.phone_display() {
+        lines.push(format!("TEL;TYPE=CELL:{}", escape(phone)));
+    }
+
+    let mut note = Vec::new();
+    if !c.need_summary.is_empty() {
+        note.push(c.need_summary.clone());
+    }
+    note.push(format!("Канал: @{}", lead.source_channel));
+    if let Some(username) = &lead.telegram_username {
+        note.push(format!("Telegram: @{}", username));
+    }
+    lines.push(format!("NOTE:{}", escape(&note.join("\n"))));
+    if let Some(url) = &lead.comment_url {
+        lines.push(format!("URL:{}", escape(url)));
+    }
+    lines.push("END:VCARD".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Escapes a property value (RFC 2426 §4).
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\r', "")
+        .replace('\n', "\\n")
+}
+
+/// Ends the line with CRLF, folding it into 75-octet chunks (continuations
+/// start with a space) without splitting a character.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::analyzed_comment;
+
+    fn lead(author: &str, username: Option<&str>, phone: Option<&str>) -> AnalyzedComment {
+        let mut c = analyzed_comment(20);
+        c.author_id = 1;
+        c.author = author.to_string();
+        c.username = username.map(str::to_string);
+        c.phone = phone.map(str::to_string);
+        c.need_summary = "Нужен CRM; срочно, до пятницы".to_string();
+        c
+    }
+
+    #[test]
+    fn writes_reachable_leads_as_escaped_cards() {
+        let leads = vec![
+            lead("Иван Петров", Some("ivan"), Some("79161234567")),
+            lead("Аноним", None, None),
+        ];
+        let vcf = leads_vcf(&leads);
+        assert!(vcf.lines().all(|line| line.len() <= 75));
+
+        let unfolded = vcf.replace("\r\n ", "");
+        assert_eq!(unfolded.matches("BEGIN:VCARD").count(), 1);
+        assert!(unfolded.contains("FN:Иван Петров\r\n"));
+        assert!(unfolded.contains("N:Петров;Иван;;;\r\n"));
+        assert!(unfolded.contains("TEL;TYPE=CELL:79161234567\r\n"));
+        assert!(unfolded.contains("NOTE:Нужен CRM\\; срочно\\, до пятницы\\nКанал: @pik\\nTelegram: @ivan\r\n"));
+        assert!(unfolded.contains("URL:https://t.me/pik/10?comment=20\r\n"));
+    }
+}
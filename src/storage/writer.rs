@@ -1,59 +1,155 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use serde::Serialize;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::analysis::{AnalyzedComment, Intent};
 use crate::config::StorageConfig;
+use crate::notify::{OutreachTemplates, Webhook};
+use super::retention::{self, RetentionPolicy};
+use super::channels::{ChannelEntry, ChannelStats};
+use super::stats::LifetimeStats;
 
-#[derive(Debug, Serialize)]
+const RETENTION_SWEEP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Day files searched for the record an edited comment replaces; Telegram only
+/// allows edits for 48 hours, which spans at most three calendar days
+const EDIT_LOOKBACK_DAYS: i64 = 3;
+
+/// Missing fields default, so reports written by older versions still load.
+#[derive(Debug, Serialize, Deserialize)]
 struct LeadEntry {
+    /// Position in the report (0 for webhook payloads)
+    #[serde(default)]
     rank: usize,
     lead_score: f32,
+    #[serde(default)]
+    sentiment: f32,
+    /// Telegram user ID (0 = unknown, as in reports written before it was stored)
+    #[serde(default)]
+    author_id: i64,
     author: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     username: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     phone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phone_e164: Option<String>,
+    /// `phone` is set but isn't a valid number (no `phone_e164`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    phone_unverified: bool,
     channel: String,
     post_id: i32,
     comment_id: i32,
     intent: Intent,
+    #[serde(default)]
     need_summary: String,
+    #[serde(default)]
     text: String,
     date: DateTime<Utc>,
-    post_url: String,
+    /// Link to the comment in its thread (the name predates that; None for
+    /// channels joined by invite link)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    post_url: Option<String>,
+    /// Suggested first-contact message for this intent, if a template is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outreach: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    reactions: Vec<(String, i32)>,
+    /// Lead comments by this person in this channel; the entry shows the best one
+    #[serde(default)]
+    comment_count: usize,
+    /// Comments the lead was classified on together, when aggregated by author
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aggregated_comment_ids: Vec<i32>,
+    #[serde(default)]
+    contacted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    contacted_at: Option<DateTime<Utc>>,
+    /// When the first of these lead comments was analyzed (None in reports
+    /// written before it was stored)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_seen: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
-struct LeadsReport {
-    generated_at: DateTime<Utc>,
-    total_leads: usize,
-    leads: Vec<LeadEntry>,
+impl LeadEntry {
+    /// Rebuilds the comment behind a stored entry. Fields the report doesn't
+    /// keep (confidence, velocity) come back empty. `analyzed_at` is the entry's
+    /// first-seen time (the report's `generated_at` for older reports), which is
+    /// what `AppState::restore_leads` counts leads as new from.
+    fn into_comment(self, generated_at: DateTime<Utc>) -> AnalyzedComment {
+        AnalyzedComment {
+            channel: self.channel,
+            post_id: self.post_id,
+            comment_id: self.comment_id,
+            author_id: self.author_id,
+            author: self.author,
+            username: self.username,
+            phone: self.phone,
+            phone_e164: self.phone_e164,
+            text: self.text,
+            date: self.date,
+            intent: self.intent,
+            confidence: 0.0,
+            is_lead: true,
+            lead_score: self.lead_score,
+            sentiment: self.sentiment,
+            need_summary: self.need_summary,
+            analyzed_at: self.first_seen.unwrap_or(generated_at),
+            comment_velocity: 0,
+            reactions: self.reactions,
+            reply_to_comment_id: None,
+            is_premium: false,
+            is_verified: false,
+            topic_id: None,
+            edited: false,
+            views: None,
+            forwards: None,
+            contacted: self.contacted,
+            contacted_at: self.contacted_at,
+            post_text: String::new(),
+            author_bio: None,
+            aggregated_comment_ids: self.aggregated_comment_ids,
+        }
+    }
 }
 
-#[derive(Debug, Default)]
-struct ChannelStat {
-    has_comments: Option<bool>,
-    comments_total: usize,
-    leads_total: usize,
+/// A lead marked (or unmarked) as contacted on the dashboard, for leads.json.
+#[derive(Debug, Clone)]
+pub struct LeadContact {
+    pub channel: String,
+    pub comment_id: i32,
+    /// None = no longer contacted
+    pub contacted_at: Option<DateTime<Utc>>,
+}
+
+/// A leads report as read back on startup; entries stay raw so one bad entry
+/// doesn't discard the rest.
+#[derive(Debug, Deserialize)]
+struct StoredLeadsReport {
+    generated_at: DateTime<Utc>,
+    #[serde(default)]
+    leads: Vec<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
-struct ChannelEntry {
-    name: String,
-    has_comments: bool,
-    comments_collected: usize,
-    leads_found: usize,
-    lead_rate: f64,
+struct LeadsReport {
+    generated_at: DateTime<Utc>,
+    total_leads: usize,
+    leads: Vec<LeadEntry>,
 }
 
 #[derive(Debug, Serialize)]
 struct ChannelsReport {
     generated_at: DateTime<Utc>,
+    /// Sum of `leads_found` over all channels
+    total_leads: usize,
+    /// Distinct people among the stored leads, across channels
+    unique_leads: usize,
     channels: Vec<ChannelEntry>,
 }
 
@@ -61,50 +157,130 @@ pub struct StorageWriter {
     data_dir: PathBuf,
     format: String,
     leads: Vec<AnalyzedComment>,
-    channel_stats: HashMap<String, ChannelStat>,
+    /// Cap on `leads` (0 = none), see `cap_leads`
+    max_leads: usize,
+    /// Pain-signal comments for pain_signals.json (only when `pain_signals_report` is on)
+    pain_signals: Vec<AnalyzedComment>,
+    channel_stats: ChannelStats,
     channel_status_rx: mpsc::Receiver<(String, bool)>,
+    /// Contacted marks from the dashboard, applied to `leads`
+    lead_contact_rx: mpsc::Receiver<LeadContact>,
+    /// All-time counters, flushed to stats.json every `stats_persist_interval`
+    lifetime: LifetimeStats,
+    outreach: OutreachTemplates,
+    lifetime_dirty: bool,
+    stats_persist_interval: Duration,
+    /// Reports with changes not yet written, flushed every `report_interval`
+    leads_dirty: bool,
+    pain_signals_dirty: bool,
+    channels_dirty: bool,
+    report_interval: Duration,
+    /// Receives each new lead as it arrives (None = off)
+    webhook: Option<Webhook>,
+    /// Today's comments for the `json` format, flushed with the reports
+    json_day: Option<JsonDay>,
+    /// Compression and deletion of old daily comment files, swept once a day
+    retention: RetentionPolicy,
 }
 
 impl StorageWriter {
-    pub fn new(config: &StorageConfig, channel_status_rx: mpsc::Receiver<(String, bool)>) -> Self {
+    pub fn new(
+        config: &StorageConfig,
+        channel_status_rx: mpsc::Receiver<(String, bool)>,
+        lead_contact_rx: mpsc::Receiver<LeadContact>,
+        lifetime: LifetimeStats,
+        outreach: OutreachTemplates,
+        webhook: Option<Webhook>,
+    ) -> Self {
+        let mut leads = load_lead_list(&config.data_dir.join("leads.json"));
+        let pain_signals = if config.pain_signals_report {
+            load_lead_list(&config.data_dir.join("pain_signals.json"))
+        } else {
+            Vec::new()
+        };
+
+        let channel_stats = ChannelStats::new(config, &lifetime, &leads);
+        cap_leads(&mut leads, config.max_leads);
+
         Self {
             data_dir: config.data_dir.clone(),
             format: config.format.clone(),
-            leads: Vec::new(),
-            channel_stats: HashMap::new(),
+            leads,
+            max_leads: config.max_leads,
+            pain_signals,
+            channel_stats,
             channel_status_rx,
+            lead_contact_rx,
+            lifetime,
+            outreach,
+            lifetime_dirty: false,
+            stats_persist_interval: Duration::from_secs(config.stats_persist_secs.max(1)),
+            leads_dirty: false,
+            pain_signals_dirty: false,
+            channels_dirty: false,
+            report_interval: Duration::from_millis(config.report_interval_ms.max(1)),
+            webhook,
+            json_day: None,
+            retention: RetentionPolicy {
+                compress_after_days: config.compress_after_days,
+                retain_days: config.retain_days,
+            },
         }
     }
 
+    /// Leads restored from leads.json (plus any added since), to seed the dashboard.
+    pub fn leads(&self) -> &[AnalyzedComment] {
+        &self.leads
+    }
+
+    /// Per-channel counts restored from stats.json, to seed /api/channels.
+    pub fn channel_stats(&self) -> &ChannelStats {
+        &self.channel_stats
+    }
+
     pub async fn run(mut self, mut rx: broadcast::Receiver<AnalyzedComment>) -> Result<()> {
         info!("Storage writer started (format: {})", self.format);
 
         std::fs::create_dir_all(&self.data_dir)
             .context("Failed to create data directory")?;
 
+        let mut status_open = true;
+        let mut contacts_open = true;
+        let mut persist_tick = tokio::time::interval(self.stats_persist_interval);
+        let mut report_tick = tokio::time::interval(self.report_interval);
+        report_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // First tick is immediate, so a sweep also runs at startup
+        let mut retention_tick = tokio::time::interval(RETENTION_SWEEP_INTERVAL);
+
         loop {
             tokio::select! {
                 result = rx.recv() => {
                     match result {
+                        Ok(comment) if comment.edited => self.record_edit(comment).await,
                         Ok(comment) => {
-                            let stat = self.channel_stats.entry(comment.channel.clone()).or_default();
-                            stat.comments_total += 1;
-                            if comment.is_lead {
-                                stat.leads_total += 1;
-                            }
+                            let is_lead = self.channel_stats.record(&comment);
+                            self.lifetime.record(&comment);
+                            self.lifetime_dirty = true;
 
                             if let Err(e) = self.write(&comment).await {
                                 error!("Failed to write comment: {:#}", e);
                             }
-                            if comment.is_lead {
-                                self.leads.push(comment);
-                                if let Err(e) = self.write_leads_report().await {
-                                    error!("Failed to write leads report: {:#}", e);
-                                }
+                            if self.channel_stats.is_pain_signal(&comment) {
+                                self.pain_signals.push(comment.clone());
+                                self.pain_signals_dirty = true;
                             }
-                            if let Err(e) = self.write_channels_report().await {
-                                error!("Failed to write channels report: {:#}", e);
+                            if is_lead {
+                                if let Some(webhook) = &self.webhook {
+                                    match serde_json::to_vec(&self.lead_entry(&comment, 0, 1)) {
+                                        Ok(body) => webhook.send(body),
+                                        Err(e) => error!("Failed to serialize webhook lead: {:#}", e),
+                                    }
+                                }
+                                self.leads.push(comment);
+                                cap_leads(&mut self.leads, self.max_leads);
+                                self.leads_dirty = true;
                             }
+                            self.channels_dirty = true;
                         }
                         Err(broadcast::error::RecvError::Lagged(n)) => {
                             tracing::warn!("Storage writer lagged, skipped {} messages", n);
@@ -116,78 +292,200 @@ impl StorageWriter {
                     }
                 }
 
-                status = self.channel_status_rx.recv() => {
-                    if let Some((channel, has_comments)) = status {
-                        self.channel_stats.entry(channel).or_default().has_comments = Some(has_comments);
-                        if let Err(e) = self.write_channels_report().await {
-                            error!("Failed to write channels report: {:#}", e);
+                _ = report_tick.tick() => self.flush_reports().await,
+
+                _ = retention_tick.tick(), if self.retention.is_enabled() => {
+                    let data_dir = self.data_dir.clone();
+                    let policy = self.retention;
+                    let today = Utc::now().date_naive();
+                    let swept = tokio::task::spawn_blocking(move || retention::sweep(&data_dir, today, policy)).await;
+                    match swept {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => error!("Comment file retention sweep failed: {:#}", e),
+                        Err(e) => error!("Comment file retention sweep panicked: {}", e),
+                    }
+                }
+
+                _ = persist_tick.tick() => {
+                    if self.lifetime_dirty {
+                        if let Err(e) = self.write_lifetime_stats().await {
+                            error!("Failed to write stats.json: {:#}", e);
                         }
                     }
                 }
+
+                // Closes when the scraper stops, before the comment stream does
+                status = self.channel_status_rx.recv(), if status_open => {
+                    match status {
+                        Some((channel, has_comments)) => {
+                            self.channel_stats.set_has_comments(channel, has_comments);
+                            self.channels_dirty = true;
+                        }
+                        None => status_open = false,
+                    }
+                }
+
+                contact = self.lead_contact_rx.recv(), if contacts_open => {
+                    match contact {
+                        Some(contact) => self.apply_contact(&contact),
+                        None => contacts_open = false,
+                    }
+                }
             }
         }
 
+        self.flush_reports().await;
+        if self.lifetime_dirty {
+            self.write_lifetime_stats().await?;
+        }
+
         Ok(())
     }
 
-    async fn write_channels_report(&self) -> Result<()> {
-        let mut entries: Vec<ChannelEntry> = self.channel_stats
-            .iter()
-            .map(|(name, stat)| {
-                let lead_rate = if stat.comments_total > 0 {
-                    stat.leads_total as f64 / stat.comments_total as f64
-                } else {
-                    0.0
-                };
-                ChannelEntry {
-                    name: name.clone(),
-                    has_comments: stat.has_comments.unwrap_or(false),
-                    comments_collected: stat.comments_total,
-                    leads_found: stat.leads_total,
-                    lead_rate,
+    /// An edited comment replaces its stored record and its entries in the
+    /// reports instead of being counted again.
+    async fn record_edit(&mut self, comment: AnalyzedComment) {
+        match self.update_stored(&comment).await {
+            Ok(true) => {}
+            Ok(false) => {
+                // Written before the lookback, or already removed by retention
+                if let Err(e) = self.write(&comment).await {
+                    error!("Failed to write edited comment: {:#}", e);
                 }
-            })
-            .collect();
+            }
+            Err(e) => error!("Failed to update edited comment {}: {:#}", comment.comment_id, e),
+        }
+
+        let is_lead = self.channel_stats.counts_as_lead(&comment);
+        let was_lead = replace_or_remove(&mut self.leads, &comment, is_lead);
+        cap_leads(&mut self.leads, self.max_leads);
+        if is_lead && !was_lead {
+            self.channel_stats.record_new_lead(&comment.channel);
+            if let Some(webhook) = &self.webhook {
+                match serde_json::to_vec(&self.lead_entry(&comment, 0, 1)) {
+                    Ok(body) => webhook.send(body),
+                    Err(e) => error!("Failed to serialize webhook lead: {:#}", e),
+                }
+            }
+        }
+        let is_pain_signal = self.channel_stats.is_pain_signal(&comment);
+        replace_or_remove(&mut self.pain_signals, &comment, is_pain_signal);
+
+        self.leads_dirty = true;
+        self.pain_signals_dirty = true;
+        self.channels_dirty = true;
+    }
+
+    /// Rewrites the record of an edited comment in the day file it was stored
+    /// in, searching back from today. False if no recent file has it.
+    async fn update_stored(&mut self, comment: &AnalyzedComment) -> Result<bool> {
+        let today = Utc::now().date_naive();
+        for days_back in 0..EDIT_LOOKBACK_DAYS {
+            let date = today - chrono::Duration::days(days_back);
+            let path = self
+                .data_dir
+                .join(format!("comments_{}.{}", date.format("%Y-%m-%d"), self.format));
+
+            if let Some(day) = self.json_day.as_mut().filter(|day| day.path == path) {
+                if day.replace(comment) {
+                    return Ok(true);
+                }
+                continue;
+            }
+            if !path.exists() {
+                continue;
+            }
+            let replaced = match self.format.as_str() {
+                "jsonl" => replace_jsonl(&path, comment).await?,
+                "csv" => replace_csv(&path, comment).await?,
+                "json" => {
+                    let mut day = JsonDay::open(path).await;
+                    let replaced = day.replace(comment);
+                    day.flush().await?;
+                    replaced
+                }
+                _ => anyhow::bail!("Unknown format: {}", self.format),
+            };
+            if replaced {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
 
-        entries.sort_by(|a, b| b.comments_collected.cmp(&a.comments_collected));
+    /// Sets the contacted mark on the matching stored lead comments.
+    fn apply_contact(&mut self, contact: &LeadContact) {
+        for lead in &mut self.leads {
+            if lead.comment_id == contact.comment_id && lead.channel == contact.channel {
+                lead.contacted = contact.contacted_at.is_some();
+                lead.contacted_at = contact.contacted_at;
+                self.leads_dirty = true;
+            }
+        }
+    }
+
+    /// Rewrites the reports that changed since the last flush. A failed write
+    /// stays dirty and is retried on the next tick.
+    async fn flush_reports(&mut self) {
+        if self.leads_dirty {
+            match self.write_lead_list(&self.leads, "leads.json").await {
+                Ok(()) => self.leads_dirty = false,
+                Err(e) => error!("Failed to write leads report: {:#}", e),
+            }
+        }
+        if self.pain_signals_dirty {
+            match self.write_lead_list(&self.pain_signals, "pain_signals.json").await {
+                Ok(()) => self.pain_signals_dirty = false,
+                Err(e) => error!("Failed to write pain signals report: {:#}", e),
+            }
+        }
+        if self.channels_dirty {
+            match self.write_channels_report().await {
+                Ok(()) => self.channels_dirty = false,
+                Err(e) => error!("Failed to write channels report: {:#}", e),
+            }
+        }
+        if let Some(day) = &mut self.json_day {
+            if let Err(e) = day.flush().await {
+                error!("Failed to write {}: {:#}", day.path.display(), e);
+            }
+        }
+    }
+
+    async fn write_lifetime_stats(&mut self) -> Result<()> {
+        self.lifetime.by_channel = self.channel_stats.totals();
+        self.lifetime.save(&self.data_dir).await?;
+        self.lifetime_dirty = false;
+        Ok(())
+    }
+
+    async fn write_channels_report(&self) -> Result<()> {
+        let entries = self.channel_stats.entries();
 
         let report = ChannelsReport {
             generated_at: Utc::now(),
+            total_leads: entries.iter().map(|e| e.leads_found).sum(),
+            unique_leads: self.leads.iter().map(|c| c.person_key()).collect::<HashSet<_>>().len(),
             channels: entries,
         };
 
         let json = serde_json::to_string_pretty(&report)
             .context("Failed to serialize channels report")?;
 
-        let path = self.data_dir.join("channels.json");
-        tokio::fs::write(&path, json.as_bytes())
-            .await
-            .context("Failed to write channels.json")?;
+        write_atomic(&self.data_dir.join("channels.json"), json.as_bytes()).await?;
 
         Ok(())
     }
 
-    async fn write_leads_report(&self) -> Result<()> {
-        let mut sorted = self.leads.clone();
-        sorted.sort_by(|a, b| b.lead_score.partial_cmp(&a.lead_score).unwrap_or(std::cmp::Ordering::Equal));
-
-        let entries: Vec<LeadEntry> = sorted
-            .iter()
+    /// Writes `leads`, one entry per person and channel, ranked by lead_score as a
+    /// LeadsReport to `filename` in the data dir.
+    async fn write_lead_list(&self, leads: &[AnalyzedComment], filename: &str) -> Result<()> {
+        let entries: Vec<LeadEntry> = best_per_author(leads)
+            .into_iter()
             .enumerate()
-            .map(|(i, c)| LeadEntry {
-                rank: i + 1,
-                lead_score: c.lead_score,
-                author: c.author.clone(),
-                username: c.username.clone(),
-                phone: c.phone.clone(),
-                channel: c.channel.clone(),
-                post_id: c.post_id,
-                comment_id: c.comment_id,
-                intent: c.intent,
-                need_summary: c.need_summary.clone(),
-                text: c.text.clone(),
-                date: c.date,
-                post_url: format!("https://t.me/{}/{}", c.channel, c.post_id),
+            .map(|(i, (c, comment_count, first_seen))| LeadEntry {
+                first_seen: Some(first_seen),
+                ..self.lead_entry(c, i + 1, comment_count)
             })
             .collect();
 
@@ -200,79 +498,742 @@ impl StorageWriter {
         let json = serde_json::to_string_pretty(&report)
             .context("Failed to serialize leads report")?;
 
-        let path = self.data_dir.join("leads.json");
-        tokio::fs::write(&path, json.as_bytes())
-            .await
-            .context("Failed to write leads.json")?;
+        write_atomic(&self.data_dir.join(filename), json.as_bytes()).await?;
 
-        info!("leads.json updated ({} leads)", report.total_leads);
+        info!("{} updated ({} leads)", filename, report.total_leads);
         Ok(())
     }
 
-    async fn write(&self, comment: &AnalyzedComment) -> Result<()> {
+    fn lead_entry(&self, c: &AnalyzedComment, rank: usize, comment_count: usize) -> LeadEntry {
+        LeadEntry {
+            rank,
+            lead_score: c.lead_score,
+            sentiment: c.sentiment,
+            author_id: c.author_id,
+            author: c.author.clone(),
+            username: c.username.clone(),
+            phone: c.phone.clone(),
+            phone_e164: c.phone_e164.clone(),
+            phone_unverified: c.phone_unverified(),
+            channel: c.channel.clone(),
+            post_id: c.post_id,
+            comment_id: c.comment_id,
+            intent: c.intent.clone(),
+            need_summary: c.need_summary.clone(),
+            text: c.text.clone(),
+            date: c.date,
+            post_url: c.comment_url(),
+            outreach: self.outreach.render(c),
+            reactions: c.reactions.clone(),
+            comment_count,
+            aggregated_comment_ids: c.aggregated_comment_ids.clone(),
+            contacted: c.contacted,
+            contacted_at: c.contacted_at,
+            first_seen: Some(c.analyzed_at),
+        }
+    }
+
+    async fn write(&mut self, comment: &AnalyzedComment) -> Result<()> {
         let date_str = Utc::now().format("%Y-%m-%d").to_string();
         let filename = format!("comments_{}.{}", date_str, self.format);
         let path = self.data_dir.join(filename);
 
         match self.format.as_str() {
-            "jsonl" => self.write_jsonl(&path, comment).await,
+            "jsonl" => append_jsonl(&path, comment).await,
             "csv" => self.write_csv(&path, comment).await,
+            "json" => self.buffer_json(path, comment).await,
             _ => anyhow::bail!("Unknown format: {}", self.format),
         }
     }
 
-    async fn write_jsonl(&self, path: &PathBuf, comment: &AnalyzedComment) -> Result<()> {
+    /// Adds the comment to today's JSON array; the file is rewritten on the next
+    /// report flush. A new day first finishes the previous day's file.
+    async fn buffer_json(&mut self, path: PathBuf, comment: &AnalyzedComment) -> Result<()> {
+        if self.json_day.as_ref().is_none_or(|day| day.path != path) {
+            if let Some(mut previous) = self.json_day.take() {
+                previous.flush().await?;
+            }
+            self.json_day = Some(JsonDay::open(path).await);
+        }
+        if let Some(day) = &mut self.json_day {
+            day.comments.push(comment.clone());
+            day.dirty = true;
+        }
+        Ok(())
+    }
+
+    async fn write_csv(&self, path: &Path, comment: &AnalyzedComment) -> Result<()> {
         use tokio::io::AsyncWriteExt;
 
-        let json = serde_json::to_string(comment).context("Failed to serialize comment")?;
-        let line = format!("{}\n", json);
+        upgrade_csv_header(path, &csv_header(comment)?).await?;
+        let exists = path.exists();
+        let mut csv = csv::WriterBuilder::new()
+            .has_headers(!exists)
+            .from_writer(Vec::new());
+        csv.serialize(CsvRow::from(comment))
+            .context("Failed to serialize CSV row")?;
+        let bytes = csv.into_inner().context("Failed to finish CSV row")?;
 
         let mut file = tokio::fs::OpenOptions::new()
             .create(true)
             .append(true)
             .open(path)
             .await
-            .context("Failed to open JSONL file")?;
+            .context("Failed to open CSV file")?;
+        file.write_all(&bytes).await?;
+        Ok(())
+    }
+}
 
-        file.write_all(line.as_bytes())
-            .await
-            .context("Failed to write to JSONL file")?;
+/// One day's comments for the `json` format, kept in memory and written out
+/// as a single array, so the file is always valid JSON.
+struct JsonDay {
+    path: PathBuf,
+    comments: Vec<AnalyzedComment>,
+    dirty: bool,
+}
+
+impl JsonDay {
+    /// Continues the array already written today (e.g. before a restart). A file
+    /// that doesn't parse is moved aside to `<name>.bad` rather than overwritten.
+    async fn open(path: PathBuf) -> Self {
+        let comments = match tokio::fs::read(&path).await {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(comments) => comments,
+                Err(e) => {
+                    let mut bad = path.as_os_str().to_owned();
+                    bad.push(".bad");
+                    warn!("{} is not a JSON array of comments ({}), moving it aside", path.display(), e);
+                    if let Err(e) = tokio::fs::rename(&path, &bad).await {
+                        error!("Failed to move {} aside: {}", path.display(), e);
+                    }
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+        Self { path, comments, dirty: false }
+    }
+
+    /// Swaps in an edited comment for its earlier record; false if this day doesn't have it.
+    fn replace(&mut self, comment: &AnalyzedComment) -> bool {
+        match self.comments.iter_mut().find(|c| c.same_comment(comment)) {
+            Some(stored) => {
+                *stored = comment.clone();
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
 
+    async fn flush(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let json = serde_json::to_vec(&self.comments).context("Failed to serialize comments")?;
+        write_atomic(&self.path, &json).await?;
+        self.dirty = false;
         Ok(())
     }
+}
 
-    async fn write_csv(&self, path: &PathBuf, comment: &AnalyzedComment) -> Result<()> {
-        use tokio::io::AsyncWriteExt;
+/// Puts `comment` in place of its earlier version in `list` if it still belongs
+/// there (`keep`), otherwise drops it. The contacted mark carries over. Returns
+/// whether an earlier version was in the list.
+fn replace_or_remove(list: &mut Vec<AnalyzedComment>, comment: &AnalyzedComment, keep: bool) -> bool {
+    let previous = list.iter().position(|c| c.same_comment(comment)).map(|i| list.remove(i));
+    if keep {
+        let mut comment = comment.clone();
+        if let Some(previous) = &previous {
+            comment.contacted = previous.contacted;
+            comment.contacted_at = previous.contacted_at;
+        }
+        list.push(comment);
+    }
+    previous.is_some()
+}
 
-        let exists = path.exists();
+/// Keeps the `max` best-scoring leads (0 = all), dropping the rest. A capped
+/// list ends up sorted best first.
+pub fn cap_leads(leads: &mut Vec<AnalyzedComment>, max: usize) {
+    if max == 0 || leads.len() <= max {
+        return;
+    }
+    leads.sort_by(|a, b| b.lead_score.partial_cmp(&a.lead_score).unwrap_or(std::cmp::Ordering::Equal));
+    leads.truncate(max);
+}
 
-        let mut file = tokio::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path)
-            .await
-            .context("Failed to open CSV file")?;
+/// Identifies a stored comment without parsing the whole record.
+#[derive(Deserialize)]
+struct StoredId {
+    channel: String,
+    comment_id: i32,
+}
+
+/// Replaces the line holding `comment` in a JSONL day file; false if it has none.
+async fn replace_jsonl(path: &Path, comment: &AnalyzedComment) -> Result<bool> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut replaced = false;
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        let matches = !replaced
+            && serde_json::from_str::<StoredId>(line)
+                .is_ok_and(|id| id.channel == comment.channel && id.comment_id == comment.comment_id);
+        if matches {
+            out.push_str(&serde_json::to_string(comment).context("Failed to serialize comment")?);
+            replaced = true;
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+
+    if replaced {
+        write_atomic(path, out.as_bytes()).await?;
+    }
+    Ok(replaced)
+}
+
+/// Replaces the row holding `comment` in a CSV day file; false if it has none.
+async fn replace_csv(path: &Path, comment: &AnalyzedComment) -> Result<bool> {
+    upgrade_csv_header(path, &csv_header(comment)?).await?;
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut reader = csv::Reader::from_reader(bytes.as_slice());
+    let headers = reader.headers().context("Failed to read CSV header")?.clone();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let (Some(channel_col), Some(id_col)) = (column("channel"), column("comment_id")) else {
+        return Ok(false);
+    };
+    let comment_id = comment.comment_id.to_string();
+
+    let mut replaced = false;
+    let mut out = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+    out.write_record(&headers).context("Failed to write CSV header")?;
+    for record in reader.records() {
+        let record = record.context("Failed to read CSV row")?;
+        if !replaced
+            && record.get(channel_col) == Some(comment.channel.as_str())
+            && record.get(id_col) == Some(comment_id.as_str())
+        {
+            out.serialize(CsvRow::from(comment)).context("Failed to serialize CSV row")?;
+            replaced = true;
+        } else {
+            out.write_record(&record).context("Failed to write CSV row")?;
+        }
+    }
+
+    if replaced {
+        let bytes = out.into_inner().context("Failed to finish CSV file")?;
+        write_atomic(path, &bytes).await?;
+    }
+    Ok(replaced)
+}
+
+/// The current `CsvRow` column names, as written above `comment`.
+fn csv_header(comment: &AnalyzedComment) -> Result<csv::StringRecord> {
+    let mut csv = csv::Writer::from_writer(Vec::new());
+    csv.serialize(CsvRow::from(comment))
+        .context("Failed to serialize CSV row")?;
+    let bytes = csv.into_inner().context("Failed to finish CSV row")?;
+    let header = csv::Reader::from_reader(bytes.as_slice())
+        .headers()
+        .context("Failed to read CSV header")?
+        .clone();
+    Ok(header)
+}
+
+/// Brings a day file written under older `CsvRow` columns (e.g. before views and
+/// forwards) up to `header`: each value moves to its column by name and new
+/// columns are left empty, so rows appended or replaced afterwards line up.
+async fn upgrade_csv_header(path: &Path, header: &csv::StringRecord) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    // Only the first line is read when the header is already current
+    let current = csv::Reader::from_path(path)
+        .and_then(|mut reader| reader.headers().cloned())
+        .with_context(|| format!("Failed to read CSV header of {}", path.display()))?;
+    if current == *header {
+        return Ok(());
+    }
+
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut reader = csv::Reader::from_reader(bytes.as_slice());
+    let columns: Vec<Option<usize>> = header
+        .iter()
+        .map(|name| current.iter().position(|h| h == name))
+        .collect();
+    let mut out = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+    out.write_record(header).context("Failed to write CSV header")?;
+    for record in reader.records() {
+        let record = record.context("Failed to read CSV row")?;
+        let row = columns.iter().map(|c| c.and_then(|i| record.get(i)).unwrap_or(""));
+        out.write_record(row).context("Failed to write CSV row")?;
+    }
+    let bytes = out.into_inner().context("Failed to finish CSV file")?;
+    write_atomic(path, &bytes).await?;
+    warn!("{} had older CSV columns, rewrote it with the current ones", path.display());
+    Ok(())
+}
+
+/// One comment as a CSV line; nested fields are flattened to text.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    channel: String,
+    post_id: i32,
+    comment_id: i32,
+    author_id: i64,
+    author: String,
+    username: Option<String>,
+    phone: Option<String>,
+    phone_e164: Option<String>,
+    text: String,
+    date: DateTime<Utc>,
+    intent: String,
+    intent_label: String,
+    confidence: f32,
+    is_lead: bool,
+    lead_score: f32,
+    sentiment: f32,
+    need_summary: String,
+    analyzed_at: DateTime<Utc>,
+    comment_velocity: u32,
+    /// As in `AnalyzedComment::reactions_display`, e.g. "👍 3 ❤️ 1"
+    reactions: String,
+    reply_to_comment_id: Option<i32>,
+    views: Option<i32>,
+    forwards: Option<i32>,
+}
+
+impl From<&AnalyzedComment> for CsvRow {
+    fn from(c: &AnalyzedComment) -> Self {
+        Self {
+            channel: c.channel.clone(),
+            post_id: c.post_id,
+            comment_id: c.comment_id,
+            author_id: c.author_id,
+            author: c.author.clone(),
+            username: c.username.clone(),
+            phone: c.phone.clone(),
+            phone_e164: c.phone_e164.clone(),
+            text: c.text.clone(),
+            date: c.date,
+            intent: c.intent.key().to_string(),
+            intent_label: c.intent.label(),
+            confidence: c.confidence,
+            is_lead: c.is_lead,
+            lead_score: c.lead_score,
+            sentiment: c.sentiment,
+            need_summary: c.need_summary.clone(),
+            analyzed_at: c.analyzed_at,
+            comment_velocity: c.comment_velocity,
+            reactions: c.reactions_display(),
+            reply_to_comment_id: c.reply_to_comment_id,
+            views: c.views,
+            forwards: c.forwards,
+        }
+    }
+}
+
+/// One row of the leads CSV export (`GET /api/leads.csv`).
+#[derive(Debug, Serialize)]
+struct LeadCsvRow<'a> {
+    rank: usize,
+    lead_score: f32,
+    author: &'a str,
+    username: Option<&'a str>,
+    phone: Option<&'a str>,
+    channel: &'a str,
+    post_url: Option<String>,
+    intent: &'a str,
+    need_summary: &'a str,
+    date: DateTime<Utc>,
+}
+
+/// `leads` as CSV, deduplicated and ranked like leads.json. Starts with a UTF-8
+/// BOM so Excel doesn't mangle Cyrillic.
+pub fn leads_csv(leads: &[AnalyzedComment]) -> Result<Vec<u8>> {
+    let mut csv = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+    for (i, (c, _, _)) in best_per_author(leads).into_iter().enumerate() {
+        csv.serialize(LeadCsvRow {
+            rank: i + 1,
+            lead_score: c.lead_score,
+            author: &c.author,
+            username: c.username.as_deref(),
+            phone: c.phone_display(),
+            channel: &c.channel,
+            post_url: c.comment_url(),
+            intent: c.intent.key(),
+            need_summary: &c.need_summary,
+            date: c.date,
+        })
+        .context("Failed to serialize CSV row")?;
+    }
+    csv.into_inner().context("Failed to finish leads CSV")
+}
+
+/// Reads back a report written by `write_lead_list`, so leads survive restarts.
+/// A missing or unreadable file gives no leads; unreadable entries are skipped.
+fn load_lead_list(path: &Path) -> Vec<AnalyzedComment> {
+    if !path.exists() {
+        return Vec::new();
+    }
+    let report = std::fs::read_to_string(path)
+        .context("Failed to read report")
+        .and_then(|text| {
+            serde_json::from_str::<StoredLeadsReport>(&text).context("Failed to parse report")
+        });
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("Ignoring {}: {:#}", path.display(), e);
+            return Vec::new();
+        }
+    };
+
+    let mut leads = Vec::new();
+    for value in report.leads {
+        match serde_json::from_value::<LeadEntry>(value) {
+            Ok(entry) => leads.push(entry.into_comment(report.generated_at)),
+            Err(e) => warn!("Skipping bad lead in {}: {}", path.display(), e),
+        }
+    }
+    info!("Restored {} leads from {}", leads.len(), path.display());
+    leads
+}
 
-        if !exists {
-            let header = "channel,post_id,comment_id,author,text,date,intent,confidence,analyzed_at\n";
-            file.write_all(header.as_bytes()).await?;
+/// The highest-scoring comment per (username or author name, channel) with the
+/// number of comments in its group and the earliest analysis among them, ranked
+/// by lead_score.
+fn best_per_author(leads: &[AnalyzedComment]) -> Vec<(&AnalyzedComment, usize, DateTime<Utc>)> {
+    let mut groups: HashMap<(String, String), (&AnalyzedComment, usize, DateTime<Utc>)> = HashMap::new();
+    for c in leads {
+        let key = (c.person_key(), c.channel.trim_start_matches('@').to_lowercase());
+        let group = groups.entry(key).or_insert((c, 0, c.analyzed_at));
+        group.1 += 1;
+        group.2 = group.2.min(c.analyzed_at);
+        if c.lead_score > group.0.lead_score {
+            group.0 = c;
         }
+    }
+
+    let mut best: Vec<_> = groups.into_values().collect();
+    best.sort_by(|a, b| {
+        b.0.lead_score
+            .partial_cmp(&a.0.lead_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.0.date.cmp(&a.0.date))
+    });
+    best
+}
+
+/// Replaces `path` with `bytes` via a sibling `.tmp` file and a rename, so
+/// readers see either the old or the new contents, never a partial write.
+pub async fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    tokio::fs::write(&tmp, bytes)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
 
-        let escaped_text = comment.text.replace('"', "\"\"");
-        let line = format!(
-            "{},{},{},\"{}\",\"{}\",{},{},{:.2},{}\n",
-            comment.channel,
-            comment.post_id,
-            comment.comment_id,
-            comment.author.replace('"', "\"\""),
-            escaped_text,
-            comment.date.to_rfc3339(),
-            comment.intent,
-            comment.confidence,
-            comment.analyzed_at.to_rfc3339(),
+/// Appends `value` as one JSON line to `path`, creating the file if needed.
+pub async fn append_jsonl<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let json = serde_json::to_string(value).context("Failed to serialize JSONL record")?;
+    let line = format!("{}\n", json);
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    file.write_all(line.as_bytes())
+        .await
+        .with_context(|| format!("Failed to write to {}", path.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::analyzed_comment;
+
+    fn lead(username: Option<&str>, author: &str, channel: &str, score: f32) -> AnalyzedComment {
+        let mut c = analyzed_comment((score * 100.0) as i32);
+        c.channel = channel.to_string();
+        c.post_id = 1;
+        c.author = author.to_string();
+        c.username = username.map(str::to_string);
+        c.confidence = 0.9;
+        c.is_lead = true;
+        c.lead_score = score;
+        c
+    }
+
+    #[test]
+    fn keeps_best_comment_per_author_and_channel() {
+        let leads = vec![
+            lead(Some("ivan"), "Ivan", "pik", 0.5),
+            lead(Some("Ivan"), "Ivan P.", "pik", 0.9),
+            lead(Some("ivan"), "Ivan", "other", 0.6),
+            lead(None, "Anna", "pik", 0.7),
+        ];
+
+        let best: Vec<(i32, usize)> = best_per_author(&leads)
+            .into_iter()
+            .map(|(c, count, _)| (c.comment_id, count))
+            .collect();
+        assert_eq!(best, vec![(90, 2), (70, 1), (60, 1)]);
+    }
+
+    #[test]
+    fn cap_keeps_the_best_scoring_leads() {
+        let mut leads = vec![lead(None, "a", "pik", 0.5), lead(None, "b", "pik", 0.9), lead(None, "c", "pik", 0.7)];
+        cap_leads(&mut leads, 0);
+        assert_eq!(leads.len(), 3);
+
+        cap_leads(&mut leads, 2);
+        let ids: Vec<i32> = leads.iter().map(|c| c.comment_id).collect();
+        assert_eq!(ids, vec![90, 70]);
+    }
+
+    #[test]
+    fn leads_csv_leaves_missing_fields_blank() {
+        let leads = vec![lead(None, "Иван", "chan", 0.8), lead(Some("anna"), "Анна", "chan", 0.9)];
+        let bytes = leads_csv(&leads).unwrap();
+        let text = std::str::from_utf8(&bytes[3..]).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "rank,lead_score,author,username,phone,channel,post_url,intent,need_summary,date"
         );
+        assert!(lines[1].starts_with("1,0.9,Анна,anna,,chan,"));
+        assert!(lines[2].starts_with("2,0.8,Иван,,,chan,"));
+        assert!(!text.contains("None"));
+    }
 
-        file.write_all(line.as_bytes()).await?;
-        Ok(())
+    #[test]
+    fn restores_leads_from_older_report() {
+        let dir = std::env::temp_dir().join(format!("atento-writer-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("leads.json");
+        std::fs::write(
+            &path,
+            r#"{"generated_at": "2025-01-01T00:00:00Z", "total_leads": 2, "leads": [
+                {"lead_score": 0.8, "author_id": 42, "author": "Ivan", "channel": "pik", "post_id": 1,
+                 "comment_id": 7, "intent": "marketer", "date": "2025-01-01T00:00:00Z"},
+                {"author": "broken"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let leads = load_lead_list(&path);
+        assert_eq!(leads.len(), 1);
+        assert_eq!(leads[0].comment_id, 7);
+        assert_eq!(leads[0].author_id, 42);
+        assert!(leads[0].is_lead);
+        assert!(load_lead_list(&dir.join("missing.json")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn leads_keep_their_first_seen_time_across_restarts() {
+        let dir = std::env::temp_dir().join(format!("atento-writer-first-seen-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let config: StorageConfig =
+            toml::from_str(&format!("data_dir = {:?}\nformat = \"jsonl\"", dir)).unwrap();
+        let open = || {
+            let (_, status_rx) = mpsc::channel(1);
+            let (_, contact_rx) = mpsc::channel(1);
+            StorageWriter::new(
+                &config,
+                status_rx,
+                contact_rx,
+                LifetimeStats::default(),
+                OutreachTemplates::new(HashMap::new()),
+                None,
+            )
+        };
+
+        let first_seen = "2025-01-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut first = lead(Some("ivan"), "Ivan", "pik", 0.5);
+        first.analyzed_at = first_seen;
+        let mut best = lead(Some("ivan"), "Ivan", "pik", 0.9);
+        best.analyzed_at = first_seen + chrono::Duration::hours(2);
+        let mut writer = open();
+        writer.leads = vec![first, best];
+        writer.write_lead_list(&writer.leads, "leads.json").await.unwrap();
+
+        let restored = open();
+        assert_eq!(restored.leads().len(), 1);
+        assert_eq!(restored.leads()[0].comment_id, 90);
+        assert_eq!(restored.leads()[0].analyzed_at, first_seen);
+    }
+
+    #[test]
+    fn csv_rows_round_trip_awkward_text() {
+        let mut comment = lead(Some("ivan"), "Иван \"Ваня\", CEO", "pik", 0.8);
+        comment.text = "Первая строка, с запятой\nвторая \"в кавычках\"\r\nтретья".to_string();
+        comment.need_summary = "Ищет клиентов, срочно".to_string();
+
+        let mut csv = csv::Writer::from_writer(Vec::new());
+        csv.serialize(CsvRow::from(&comment)).unwrap();
+        csv.serialize(CsvRow::from(&comment)).unwrap();
+        let bytes = csv.into_inner().unwrap();
+
+        let rows: Vec<CsvRow> = csv::Reader::from_reader(bytes.as_slice())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].text, comment.text);
+        assert_eq!(rows[0].author, comment.author);
+        assert_eq!(rows[0].need_summary, comment.need_summary);
+        assert_eq!(rows[1].lead_score, 0.8);
+        assert!(rows[1].is_lead);
+    }
+
+    #[tokio::test]
+    async fn concurrent_readers_never_see_partial_reports() {
+        let dir = std::env::temp_dir().join(format!("atento-atomic-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("leads.json");
+        write_atomic(&path, b"[]").await.unwrap();
+
+        let writer_path = path.clone();
+        let writer = tokio::spawn(async move {
+            for i in 0..200 {
+                let report: Vec<String> = (0..(i % 7) * 500).map(|n| n.to_string()).collect();
+                let json = serde_json::to_string(&report).unwrap();
+                write_atomic(&writer_path, json.as_bytes()).await.unwrap();
+            }
+        });
+
+        while !writer.is_finished() {
+            let text = tokio::fs::read_to_string(&path).await.unwrap();
+            assert!(serde_json::from_str::<Vec<String>>(&text).is_ok(), "partial report: {}", text.len());
+            tokio::task::yield_now().await;
+        }
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn json_day_continues_its_array_and_sets_bad_files_aside() {
+        let dir = std::env::temp_dir().join(format!("atento-json-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("comments_2025-01-01.json");
+        let _ = std::fs::remove_file(&path);
+
+        let mut day = JsonDay::open(path.clone()).await;
+        day.comments.push(lead(None, "Иван", "chan", 0.8));
+        day.dirty = true;
+        day.flush().await.unwrap();
+
+        let mut day = JsonDay::open(path.clone()).await;
+        assert_eq!(day.comments.len(), 1);
+        day.comments.push(lead(Some("anna"), "Анна", "chan", 0.9));
+        day.dirty = true;
+        day.flush().await.unwrap();
+        let stored: Vec<AnalyzedComment> = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(stored.len(), 2);
+
+        std::fs::write(&path, "[{\"truncated\"").unwrap();
+        assert!(JsonDay::open(path.clone()).await.comments.is_empty());
+        assert!(dir.join("comments_2025-01-01.json.bad").exists());
+    }
+
+    #[tokio::test]
+    async fn edited_comment_replaces_its_stored_record() {
+        let dir = std::env::temp_dir().join(format!("atento-edit-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = lead(Some("anna"), "Анна", "chan", 0.2);
+        let other = lead(Some("ivan"), "Иван", "chan", 0.5);
+        let mut edited = original.clone();
+        edited.text = "Пишите: +7 900 000-00-00".to_string();
+        edited.edited = true;
+
+        let jsonl = dir.join("comments.jsonl");
+        let _ = std::fs::remove_file(&jsonl);
+        for c in [&original, &other] {
+            append_jsonl(&jsonl, c).await.unwrap();
+        }
+        assert!(replace_jsonl(&jsonl, &edited).await.unwrap());
+        let lines: Vec<AnalyzedComment> = std::fs::read_to_string(&jsonl)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].edited && lines[0].text == edited.text);
+        assert!(!lines[1].edited);
+
+        let mut csv = csv::Writer::from_writer(Vec::new());
+        csv.serialize(CsvRow::from(&other)).unwrap();
+        csv.serialize(CsvRow::from(&original)).unwrap();
+        let path = dir.join("comments.csv");
+        std::fs::write(&path, csv.into_inner().unwrap()).unwrap();
+        assert!(replace_csv(&path, &edited).await.unwrap());
+        let rows: Vec<CsvRow> = csv::Reader::from_path(&path)
+            .unwrap()
+            .deserialize()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].text, edited.text);
+
+        let unknown = lead(None, "Олег", "chan", 0.3);
+        assert!(!replace_csv(&path, &unknown).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn csv_file_with_older_columns_is_upgraded() {
+        let dir = std::env::temp_dir().join(format!("atento-csv-upgrade-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let original = lead(Some("anna"), "Анна", "chan", 0.2);
+        let mut edited = original.clone();
+        edited.text = "Пишите в личку".to_string();
+        edited.edited = true;
+
+        // A day file from before the views and forwards columns
+        let mut full = csv::Writer::from_writer(Vec::new());
+        full.serialize(CsvRow::from(&original)).unwrap();
+        let full = full.into_inner().unwrap();
+        let mut reader = csv::Reader::from_reader(full.as_slice());
+        let header = reader.headers().unwrap().clone();
+        let old_columns = header.len() - 2;
+        let mut old = csv::Writer::from_writer(Vec::new());
+        old.write_record(header.iter().take(old_columns)).unwrap();
+        for record in reader.records() {
+            old.write_record(record.unwrap().iter().take(old_columns)).unwrap();
+        }
+        let path = dir.join("comments_old.csv");
+        std::fs::write(&path, old.into_inner().unwrap()).unwrap();
+
+        assert!(replace_csv(&path, &edited).await.unwrap());
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(reader.headers().unwrap(), &header);
+        let rows: Vec<CsvRow> = reader.deserialize().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].text, edited.text);
     }
 }
@@ -0,0 +1,65 @@
+/// A `channels` config entry, in any of the accepted forms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelRef {
+    /// Public channel: `name`, `@name` or `https://t.me/name`
+    Username(String),
+    /// Bare channel ID, from `-1001234567890` or `1234567890`
+    Id(i64),
+    /// Invite hash, from `https://t.me/+hash` or `https://t.me/joinchat/hash`
+    Invite(String),
+}
+
+impl ChannelRef {
+    pub fn parse(entry: &str) -> Self {
+        let entry = entry.trim();
+        let path = entry
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .strip_prefix("t.me/");
+
+        if let Some(path) = path {
+            if let Some(hash) = path.strip_prefix('+').or_else(|| path.strip_prefix("joinchat/")) {
+                return ChannelRef::Invite(hash.trim_end_matches('/').to_string());
+            }
+            return ChannelRef::Username(path.trim_end_matches('/').to_string());
+        }
+
+        if let Ok(id) = entry.parse::<i64>() {
+            // Bot API style "-100" prefix marks a channel; MTProto wants the bare ID
+            let bare = entry
+                .strip_prefix("-100")
+                .and_then(|rest| rest.parse().ok())
+                .unwrap_or(id.abs());
+            return ChannelRef::Id(bare);
+        }
+
+        ChannelRef::Username(entry.trim_start_matches('@').to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_usernames() {
+        assert_eq!(ChannelRef::parse("@pik_group"), ChannelRef::Username("pik_group".into()));
+        assert_eq!(ChannelRef::parse("pik_group"), ChannelRef::Username("pik_group".into()));
+        assert_eq!(
+            ChannelRef::parse("https://t.me/pik_group/"),
+            ChannelRef::Username("pik_group".into())
+        );
+    }
+
+    #[test]
+    fn parses_numeric_ids() {
+        assert_eq!(ChannelRef::parse("-1001234567890"), ChannelRef::Id(1234567890));
+        assert_eq!(ChannelRef::parse("1234567890"), ChannelRef::Id(1234567890));
+    }
+
+    #[test]
+    fn parses_invite_links() {
+        assert_eq!(ChannelRef::parse("https://t.me/+AbCdEf123"), ChannelRef::Invite("AbCdEf123".into()));
+        assert_eq!(ChannelRef::parse("t.me/joinchat/AbCdEf123"), ChannelRef::Invite("AbCdEf123".into()));
+    }
+}
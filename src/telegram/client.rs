@@ -2,46 +2,235 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use grammers_client::Client;
 use grammers_session::storages::MemorySession;
+use grammers_session::types::{PeerAuth, PeerId, PeerRef};
 use grammers_tl_types as tl;
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
-use crate::config::TelegramConfig;
+use crate::config::{load_channels_file, proxy_endpoint, ChannelSpec, TelegramConfig};
+use super::channel_ref::ChannelRef;
+use super::cursor::{ChannelCursor, PollCursor, SeenComments};
+use super::flood::{classify, flood_wait_secs, ErrorKind};
+use super::health::TelegramHealth;
+use super::phone;
 use super::types::RawComment;
 
+/// How often the scraper repeats its warning while the channel list is empty
+const EMPTY_CHANNELS_WARN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Max `GetDialogs` pages searched when resolving a numeric channel ID
+const DIALOG_PAGES: usize = 10;
+
+/// Replies requested per `GetReplies` page (Telegram's maximum is 100)
+const REPLIES_PAGE_SIZE: i32 = 100;
+/// Posts requested per `GetHistory` page (Telegram's maximum is 100)
+const HISTORY_PAGE_SIZE: i32 = 100;
+/// Latest posts whose threads are polled (outside backfill)
+const RECENT_POSTS: usize = 200;
+/// Post IDs per `GetMessagesViews` request (Telegram's maximum is 100)
+const VIEWS_BATCH_SIZE: usize = 100;
+/// Between full post listings a channel is only asked for posts newer than the
+/// ones it already knows, plus those posts' reply counters
+const FULL_LISTING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(6 * 3600);
+
+/// Forum topics polled per group, most recently active first
+const FORUM_TOPICS_LIMIT: i32 = 100;
+/// ID of a forum's built-in General topic
+const GENERAL_TOPIC_ID: i32 = 1;
+
+/// Pause between backfill rounds of a channel with more comments than one cycle takes
+const BACKFILL_ROUND_PAUSE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long the startup reachability check waits for the proxy to accept a connection
+const PROXY_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Retries of a channel's post listing after a transient error (timeout, dropped
+/// connection); waits 2s, 4s, 8s, reconnecting first when the connection is gone
+const POST_FETCH_RETRIES: u32 = 3;
+
+/// Pause before each `GetFullUser` bio lookup, to stay clear of FLOOD_WAITs
+const BIO_LOOKUP_SPACING: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// A single comment as extracted from a `GetReplies` response.
+struct Reply {
+    id: i32,
+    author_id: i64,
+    /// Needed for `GetFullUser`; None for "min" users whose hash can't be used
+    access_hash: Option<i64>,
+    author: String,
+    username: Option<String>,
+    phone: Option<String>,
+    is_premium: bool,
+    is_verified: bool,
+    text: String,
+    date: DateTime<Utc>,
+    /// Unix time of the last edit, if any
+    edit_date: Option<i32>,
+    views: Option<i32>,
+    forwards: Option<i32>,
+    reactions: Vec<(String, i32)>,
+    reply_to_comment_id: Option<i32>,
+}
+
+/// Where a channel's comments live, looked up once per channel.
+#[derive(Clone)]
+enum Discussion {
+    /// No linked discussion group: comments are disabled
+    None,
+    /// A regular discussion group: one reply thread per post
+    Threads,
+    /// A forum group with topics: the monitored chat itself, or a channel's
+    /// linked group (`posts` set), whose post threads are polled as well
+    Forum { group: PeerRef, posts: bool },
+}
+
+/// A reply thread polled with `GetReplies`: a channel post or a forum topic.
+struct Thread {
+    /// Chat the thread lives in (the channel, or its forum group)
+    peer: PeerRef,
+    /// Post ID, or the topic's ID (its root message)
+    id: i32,
+    /// Post text, or the topic's title
+    text: String,
+    topic_id: Option<i32>,
+    /// Newest message in the thread as of the listing (0 = no replies yet; None =
+    /// not reported), so threads without new replies can skip `GetReplies`
+    last_reply_id: Option<i32>,
+}
+
+/// A channel's latest posts as of its last listing, so later polls only ask for
+/// what changed since.
+struct KnownPosts {
+    /// post ID → post text, newest `RECENT_POSTS` (None = not fetched yet, for
+    /// posts resumed from the poll cursor)
+    posts: BTreeMap<i32, Option<String>>,
+    /// Last full listing; after `FULL_LISTING_INTERVAL` the channel is listed again
+    listed_at: DateTime<Utc>,
+}
+
+impl KnownPosts {
+    /// The posts a channel's cursor entry was saved with, if it records a full listing.
+    fn resume(cursor: &ChannelCursor) -> Option<Self> {
+        let listed_at = cursor.listed_at?;
+        let mut ids: Vec<i32> = cursor.posts.keys().copied().filter(|&id| id > 0).collect();
+        ids.push(cursor.last_post_id);
+        ids.retain(|&id| id > 0);
+        ids.sort_unstable();
+        ids.dedup();
+        let posts: BTreeMap<i32, Option<String>> = ids
+            .into_iter()
+            .rev()
+            .take(RECENT_POSTS)
+            .map(|id| (id, None))
+            .collect();
+        (!posts.is_empty()).then_some(Self { posts, listed_at })
+    }
+}
+
+impl Thread {
+    /// Key in `seen` and the cursor. Topics are negated so they can't collide
+    /// with post IDs, which come from the channel's own ID space.
+    fn key(&self) -> i32 {
+        self.topic_id.map_or(self.id, |id| -id)
+    }
+}
+
 pub struct TelegramScraper {
     client: Client,
+    /// Kept so the client can be re-created on the same (authorized) session
+    session: Arc<MemorySession>,
+    api_id: i32,
+    /// `telegram.proxy`, reused on every reconnect
+    proxy: Option<String>,
+    /// Background task driving the sender pool; if it finishes, the connection is gone
+    runner: JoinHandle<()>,
+    health: TelegramHealth,
     channels: Vec<String>,
+    /// Per-channel `poll_interval` overrides, already raised to the floor
+    channel_intervals: HashMap<String, std::time::Duration>,
+    /// Lowest poll interval a channel override may use
+    poll_floor: std::time::Duration,
+    /// External channel list, re-read on mtime change when `channels_file_reload` is set
+    channels_file: Option<PathBuf>,
+    channels_file_reload: bool,
+    channels_file_mtime: Option<SystemTime>,
     poll_interval: std::time::Duration,
-    /// Tracks the last seen comment ID per (channel, post_id) to avoid duplicates
-    seen: HashMap<(String, i32), i32>,
-    /// Cache: channel_name → has linked discussion group (comments enabled)
-    channel_has_comments: HashMap<String, bool>,
+    /// Fraction (0.0-0.9) by which each channel's next poll is randomly shifted
+    poll_jitter: f64,
+    /// Last emitted comment ID per thread, kept per channel, to avoid duplicates
+    seen: SeenComments,
+    /// Durable copy of `seen` (plus per-channel progress), saved after each channel poll
+    cursor: PollCursor,
+    cursor_file: Option<PathBuf>,
+    /// How long after posting comments are re-checked for edits (None = never)
+    edit_window: Option<chrono::Duration>,
+    /// Emitted comments still within `edit_window`, per (channel, post_id):
+    /// comment ID → (posted, edit_date when last emitted)
+    edit_dates: HashMap<(String, i32), BTreeMap<i32, (DateTime<Utc>, Option<i32>)>>,
+    /// Set while backfilling: only posts and comments since then are fetched
+    backfill_since: Option<DateTime<Utc>>,
+    /// Per-channel recent posts, for incremental listings (not used while backfilling);
+    /// resumed from the poll cursor on startup
+    known_posts: HashMap<String, KnownPosts>,
+    /// (channel, post_id) of threads fetched before, so a post's first backlog
+    /// doesn't count as comment velocity; pruned along with `known_posts`
+    polled_posts: HashSet<(String, i32)>,
+    track_comment_velocity: bool,
+    /// Max new comments emitted per channel per poll; the rest wait for the next cycle
+    max_comments_per_cycle: usize,
+    /// Max new replies fetched per post per poll (across `GetReplies` pages)
+    max_replies_per_post: usize,
+    /// Times a request is retried after sleeping out a FLOOD_WAIT
+    flood_wait_retries: u32,
+    /// Cache: channel entry → peer, for entries given as an ID or invite link
+    resolved_peers: HashMap<String, PeerRef>,
+    /// Cache: channel_name → where its comments live (None = comments disabled)
+    channel_discussions: HashMap<String, Discussion>,
+    /// Cache: channel_name → (fetched_at, admin user IDs of channel + discussion group)
+    channel_admins: HashMap<String, (Instant, HashSet<i64>)>,
+    exclude_admins: bool,
+    admin_cache_ttl: std::time::Duration,
+    /// Region assumed for phone numbers without a country code (None = no inference)
+    default_country: Option<phonenumber::country::Id>,
+    /// Off: authors' phones are never read from Telegram, so nothing downstream has them
+    capture_phone: bool,
+    /// Off: same for usernames
+    capture_username: bool,
     /// Sends (channel_name, has_comments) to storage for channels.json
     channel_status_tx: mpsc::Sender<(String, bool)>,
+    fetch_author_bios: bool,
+    /// Cache: user ID → (fetched_at, profile bio)
+    author_bios: HashMap<i64, (Instant, Option<String>)>,
+    bio_cache_ttl: std::time::Duration,
+    max_bio_lookups_per_cycle: usize,
+    /// Bio lookups still allowed this poll cycle
+    bio_lookups_left: usize,
+    /// The current `run`'s shutdown token, so a reconnect can be cut short
+    shutdown: CancellationToken,
 }
 
 impl TelegramScraper {
     pub async fn connect(
         config: &TelegramConfig,
         channel_status_tx: mpsc::Sender<(String, bool)>,
+        health: TelegramHealth,
     ) -> Result<Self> {
-        let session = Arc::new(MemorySession::default());
-
-        let pool = grammers_client::sender::SenderPool::new(
-            session,
-            config.api_id,
-        );
-
-        let client = Client::new(pool.handle);
+        if let Some(proxy) = &config.proxy {
+            Self::check_proxy(proxy).await?;
+        }
 
-        // Run the sender pool in background
-        tokio::spawn(async move {
-            pool.runner.run().await;
-        });
+        let session = Arc::new(MemorySession::default());
+        let (client, runner) = Self::spawn_client(session.clone(), config.api_id, config.proxy.clone());
 
         if !client.is_authorized().await? {
             info!("Not authorized. Starting interactive sign-in...");
@@ -49,15 +238,201 @@ impl TelegramScraper {
         }
 
         info!("Telegram client connected and authorized");
+        health.set_connected(true);
+        health.set_authorized(true);
 
-        Ok(Self {
+        let cursor = config
+            .cursor_file
+            .as_deref()
+            .map(PollCursor::load)
+            .unwrap_or_default();
+        let seen = SeenComments::from(&cursor);
+        let known_posts: HashMap<String, KnownPosts> = cursor
+            .channels
+            .iter()
+            .filter_map(|(channel, c)| Some((channel.clone(), KnownPosts::resume(c)?)))
+            .collect();
+        if !seen.is_empty() {
+            info!(
+                "Resuming from poll cursor: {} channels, {} posts",
+                cursor.channels.len(),
+                seen.len()
+            );
+        }
+
+        let channels_file_mtime = config
+            .channels_file
+            .as_ref()
+            .and_then(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok());
+
+        let mut scraper = Self {
             client,
-            channels: config.channels.clone(),
+            session,
+            api_id: config.api_id,
+            proxy: config.proxy.clone(),
+            runner,
+            health,
+            channels: Vec::new(),
+            channel_intervals: HashMap::new(),
+            poll_floor: std::time::Duration::from_secs(config.poll_floor_secs()),
+            channels_file: config.channels_file.clone(),
+            channels_file_reload: config.channels_file_reload,
+            channels_file_mtime,
             poll_interval: std::time::Duration::from_secs(config.poll_interval_secs),
-            seen: HashMap::new(),
-            channel_has_comments: HashMap::new(),
+            poll_jitter: config.poll_jitter.clamp(0.0, 0.9),
+            seen,
+            cursor,
+            cursor_file: config.cursor_file.clone(),
+            edit_window: (config.edit_window_secs > 0)
+                .then(|| chrono::Duration::seconds(config.edit_window_secs as i64)),
+            edit_dates: HashMap::new(),
+            backfill_since: None,
+            polled_posts: HashSet::new(),
+            track_comment_velocity: config.track_comment_velocity,
+            max_comments_per_cycle: config.max_comments_per_cycle.max(1),
+            max_replies_per_post: config.max_replies_per_post.max(1),
+            flood_wait_retries: config.flood_wait_retries,
+            resolved_peers: HashMap::new(),
+            channel_discussions: HashMap::new(),
+            channel_admins: HashMap::new(),
+            known_posts,
+            exclude_admins: config.exclude_admins,
+            admin_cache_ttl: std::time::Duration::from_secs(config.admin_cache_ttl_secs),
+            // Validated in AppConfig::load_from
+            default_country: config.default_country.as_deref().and_then(|c| c.parse().ok()),
+            capture_phone: config.capture_phone,
+            capture_username: config.capture_username,
             channel_status_tx,
-        })
+            fetch_author_bios: config.fetch_author_bios,
+            author_bios: HashMap::new(),
+            bio_cache_ttl: std::time::Duration::from_secs(config.bio_cache_ttl_secs),
+            max_bio_lookups_per_cycle: config.max_bio_lookups_per_cycle,
+            bio_lookups_left: config.max_bio_lookups_per_cycle,
+            shutdown: CancellationToken::new(),
+        };
+        scraper.set_channels(&config.channels);
+        Ok(scraper)
+    }
+
+    /// Replaces the channel list and its per-channel poll intervals. Known and
+    /// polled posts of channels no longer listed are dropped.
+    fn set_channels(&mut self, channels: &[ChannelSpec]) {
+        self.channels = channels.iter().map(|c| c.name.clone()).collect();
+        let listed = &self.channels;
+        self.known_posts.retain(|channel, _| listed.contains(channel));
+        self.polled_posts
+            .retain(|(channel, _)| listed.contains(channel));
+        self.channel_intervals = channels
+            .iter()
+            .filter_map(|c| {
+                let requested = std::time::Duration::from_secs(c.poll_interval_secs?);
+                if requested < self.poll_floor {
+                    warn!(
+                        "@{}: poll_interval_secs = {} is below the safe minimum, using {}s",
+                        c.name,
+                        requested.as_secs(),
+                        self.poll_floor.as_secs()
+                    );
+                }
+                Some((c.name.clone(), requested.max(self.poll_floor)))
+            })
+            .collect();
+    }
+
+    /// Fails fast with a clear error when the proxy isn't accepting connections,
+    /// which grammers would otherwise surface as a generic I/O error on the
+    /// first request (easily mistaken for a login problem).
+    async fn check_proxy(proxy: &str) -> Result<()> {
+        // Validated in AppConfig::load_from
+        let endpoint = proxy_endpoint(proxy)?;
+        match timeout(PROXY_CONNECT_TIMEOUT, tokio::net::TcpStream::connect(&endpoint)).await {
+            Ok(Ok(_)) => {
+                info!("Connecting to Telegram through proxy {}", endpoint);
+                Ok(())
+            }
+            Ok(Err(e)) => Err(e).with_context(|| {
+                format!(
+                    "Proxy {} is unreachable (telegram.proxy); Telegram was not contacted, \
+                     so this is not a sign-in or API credentials problem",
+                    endpoint
+                )
+            }),
+            Err(_) => anyhow::bail!(
+                "Proxy {} is unreachable (telegram.proxy): no answer within {}s; Telegram was \
+                 not contacted, so this is not a sign-in or API credentials problem",
+                endpoint,
+                PROXY_CONNECT_TIMEOUT.as_secs()
+            ),
+        }
+    }
+
+    fn spawn_client(
+        session: Arc<MemorySession>,
+        api_id: i32,
+        proxy: Option<String>,
+    ) -> (Client, JoinHandle<()>) {
+        let params = grammers_client::sender::ConnectionParams {
+            proxy_url: proxy,
+            ..Default::default()
+        };
+        let pool = grammers_client::sender::SenderPool::with_configuration(session, api_id, params);
+        let client = Client::new(pool.handle);
+
+        // Run the sender pool in background
+        let runner = tokio::spawn(async move {
+            pool.runner.run().await;
+        });
+
+        (client, runner)
+    }
+
+    /// Re-creates the client on the existing session, retrying with exponential
+    /// backoff until Telegram answers. Fails if the session is no longer
+    /// authorized; gives up (still disconnected) on shutdown.
+    async fn reconnect(&mut self) -> Result<()> {
+        self.health.set_connected(false);
+        let shutdown = self.shutdown.clone();
+        let mut attempt = 0u32;
+
+        loop {
+            if shutdown.is_cancelled() {
+                return Ok(());
+            }
+            attempt += 1;
+            info!("Reconnecting to Telegram (attempt {})", attempt);
+            self.runner.abort();
+            let (client, runner) =
+                Self::spawn_client(self.session.clone(), self.api_id, self.proxy.clone());
+            self.client = client;
+            self.runner = runner;
+
+            match timeout(std::time::Duration::from_secs(15), self.client.is_authorized()).await {
+                Ok(Ok(true)) => {
+                    info!("Reconnected to Telegram");
+                    self.health.reconnected();
+                    return Ok(());
+                }
+                Ok(Ok(false)) => {
+                    self.health.set_authorized(false);
+                    self.health.poll_error("session not authorized".to_string());
+                    anyhow::bail!("Telegram session is no longer authorized; restart to sign in again");
+                }
+                Ok(Err(e)) => {
+                    warn!("Reconnect attempt {} failed: {:#}", attempt, e);
+                    self.health.poll_error(format!("reconnect failed: {}", e));
+                }
+                Err(_) => {
+                    warn!("Reconnect attempt {} timed out", attempt);
+                    self.health.poll_error("reconnect timed out".to_string());
+                }
+            }
+
+            let wait_secs = (5u64 * 2u64.pow(attempt.min(6))).min(300);
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_secs(wait_secs)) => {}
+                _ = shutdown.cancelled() => return Ok(()),
+            }
+        }
     }
 
     async fn interactive_login(client: &Client, api_hash: &str) -> Result<()> {
@@ -90,218 +465,1186 @@ impl TelegramScraper {
         Ok(())
     }
 
-    pub async fn run(mut self, tx: mpsc::Sender<RawComment>) -> Result<()> {
+    /// Polls until `shutdown` is cancelled. A poll in progress is finished first,
+    /// so cursors stay consistent with what was sent. Fails if the session is
+    /// signed out; can be called again after returning, on the same session.
+    pub async fn run(&mut self, tx: mpsc::Sender<RawComment>, shutdown: CancellationToken) -> Result<()> {
         info!("Starting Telegram scraper for channels: {:?}", self.channels);
+        self.shutdown = shutdown.clone();
+
+        let mut next_poll: HashMap<String, Instant> = HashMap::new();
+        // Channels whose latest poll failed
+        let mut failing: HashSet<String> = HashSet::new();
+        // Channels whose latest poll was cut short by `max_comments_per_cycle`
+        // (before a restart, too): polled first, and again after `poll_floor`
+        let mut catching_up: HashSet<String> = self
+            .cursor
+            .channels
+            .iter()
+            .filter(|(channel, c)| !c.caught_up && self.channels.contains(channel))
+            .map(|(channel, _)| channel.clone())
+            .collect();
+        if !catching_up.is_empty() {
+            info!("Catching up on channels cut short by the previous run: {:?}", catching_up);
+        }
+        // Last "no channels" warning, so an empty list is reported periodically
+        let mut empty_warned_at: Option<Instant> = None;
 
         loop {
-            for channel_name in &self.channels.clone() {
+            self.reload_channels_file();
+
+            if self.channels.is_empty() {
+                if empty_warned_at.is_none_or(|at| at.elapsed() >= EMPTY_CHANNELS_WARN_INTERVAL) {
+                    warn!("Channel list is empty, nothing to poll");
+                    empty_warned_at = Some(Instant::now());
+                }
+                self.health.set_no_channels(true);
+            } else if empty_warned_at.take().is_some() {
+                info!("Channel list is no longer empty, resuming polling: {:?}", self.channels);
+                self.health.set_no_channels(false);
+            }
+
+            if self.runner.is_finished() {
+                warn!("Telegram sender pool stopped, connection lost");
+                self.reconnect().await?;
+            }
+
+            // Poll only channels whose (jittered) next poll time has come
+            let now = Instant::now();
+            let mut due: Vec<String> = self
+                .channels
+                .iter()
+                .filter(|c| next_poll.get(*c).is_none_or(|at| *at <= now))
+                .cloned()
+                .collect();
+            due.sort_by_key(|c| !catching_up.contains(c));
+
+            self.bio_lookups_left = self.max_bio_lookups_per_cycle;
+            let bio_cache_ttl = self.bio_cache_ttl;
+            self.author_bios.retain(|_, (fetched_at, _)| fetched_at.elapsed() < bio_cache_ttl);
+
+            let mut transport_error = false;
+            let mut polled_ok = false;
+            for channel_name in &due {
+                if shutdown.is_cancelled() {
+                    break;
+                }
                 info!("Polling @{}", channel_name);
                 let poll_future = self.poll_channel(channel_name, &tx);
                 match timeout(std::time::Duration::from_secs(300), poll_future).await {
-                    Ok(Ok(())) => {}
-                    Ok(Err(e)) => error!("Error polling @{}: {:#}", channel_name, e),
-                    Err(_) => error!("Global timeout polling @{} (>300s), skipping", channel_name),
+                    Ok(Ok(emitted)) => {
+                        self.health.poll_ok(channel_name);
+                        polled_ok = true;
+                        failing.remove(channel_name);
+                        if emitted >= self.max_comments_per_cycle {
+                            catching_up.insert(channel_name.clone());
+                        } else {
+                            catching_up.remove(channel_name);
+                        }
+                    }
+                    Ok(Err(e)) => {
+                        let message = format!("{:#}", e);
+                        error!("Error polling @{}: {}", channel_name, message);
+                        self.health.poll_error(format!("@{}: {}", channel_name, message));
+                        transport_error |= classify(&message) == ErrorKind::Transient;
+                        failing.insert(channel_name.clone());
+                    }
+                    Err(_) => {
+                        error!("Global timeout polling @{} (>300s), skipping", channel_name);
+                        self.health.poll_error(format!("@{}: poll timeout", channel_name));
+                        transport_error = true;
+                        failing.insert(channel_name.clone());
+                    }
+                }
+                let interval = if catching_up.contains(channel_name) {
+                    self.poll_floor
+                } else {
+                    self.jittered_interval(channel_name)
+                };
+                next_poll.insert(channel_name.clone(), Instant::now() + interval);
+            }
+
+            // Only a cycle that got something polled (or had nothing to poll, which
+            // health reports as `no_channels`) counts towards readiness
+            if polled_ok || self.channels.is_empty() {
+                self.health.cycle_done();
+            }
+            if !self.health.snapshot().authorized {
+                anyhow::bail!("Telegram session is no longer authorized; restart to sign in again");
+            }
+
+            // One failing channel is usually that channel (private, renamed); a
+            // transport error, or every channel failing, points at the connection
+            failing.retain(|c| self.channels.contains(c));
+            let all_failing = !self.channels.is_empty() && failing.len() == self.channels.len();
+            if all_failing || transport_error {
+                if all_failing {
+                    warn!("All {} channels are failing, assuming connection loss", failing.len());
+                } else {
+                    warn!("Transport error while polling, assuming connection loss");
                 }
+                failing.clear();
+                self.reconnect().await?;
             }
 
-            tokio::time::sleep(self.poll_interval).await;
+            let sleep_for = self
+                .channels
+                .iter()
+                .filter_map(|c| next_poll.get(c))
+                .min()
+                .map(|at| at.saturating_duration_since(Instant::now()))
+                .unwrap_or(self.poll_interval);
+            tokio::select! {
+                _ = tokio::time::sleep(sleep_for.max(std::time::Duration::from_secs(1))) => {}
+                _ = shutdown.cancelled() => {
+                    info!("Telegram scraper stopping");
+                    return Ok(());
+                }
+            }
         }
     }
 
-    async fn poll_channel(&mut self, channel_name: &str, tx: &mpsc::Sender<RawComment>) -> Result<()> {
-        let channel = timeout(
-            std::time::Duration::from_secs(15),
-            self.client.resolve_username(channel_name),
-        )
-        .await
-        .context("Timeout resolving channel username")?
-        .context(format!("Channel @{} not found", channel_name))?
-        .context(format!("Channel @{} not found", channel_name))?;
+    /// Sends every channel's comments from the last `since` once, instead of
+    /// polling: posts are fetched back to `since` (not just the latest 200) and
+    /// older comments are skipped. A channel with more than `max_comments_per_cycle`
+    /// new comments is fetched in several rounds. FLOOD_WAITs are slept out as
+    /// in polling. With a `cursor_file`, comments already analyzed are skipped
+    /// and polling later resumes after the backfill.
+    pub async fn backfill(mut self, tx: mpsc::Sender<RawComment>, since: DateTime<Utc>) -> Result<()> {
+        self.backfill_since = Some(since);
+        let channels = self.channels.clone();
 
-        let peer_ref = timeout(
-            std::time::Duration::from_secs(10),
-            channel.to_ref(),
-        )
-        .await
-        .context("Timeout getting peer ref")?
-        .context("Cannot get peer ref for channel")?;
+        for channel_name in &channels {
+            info!("Backfilling @{} since {}", channel_name, since.format("%Y-%m-%d %H:%M"));
+            let mut sent = 0usize;
+            loop {
+                match self.poll_channel(channel_name, &tx).await {
+                    Ok(emitted) => {
+                        sent += emitted;
+                        if emitted < self.max_comments_per_cycle || tx.is_closed() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Error backfilling @{}: {:#}", channel_name, e);
+                        break;
+                    }
+                }
+                tokio::time::sleep(BACKFILL_ROUND_PAUSE).await;
+            }
+            info!("@{}: {} comments sent for analysis", channel_name, sent);
+        }
+        Ok(())
+    }
 
-        // Check once per channel if it has a linked discussion group
-        let has_comments = if let Some(&cached) = self.channel_has_comments.get(channel_name) {
-            cached
-        } else {
-            let result = self.check_has_comments(peer_ref.clone()).await;
-            info!("Channel @{}: comments enabled = {}", channel_name, result);
-            self.channel_has_comments.insert(channel_name.to_string(), result);
-            let _ = self.channel_status_tx.send((channel_name.to_string(), result)).await;
-            result
+    /// The channel's poll interval (its override or `poll_interval`) randomly
+    /// stretched or shrunk by up to `poll_jitter`, so channels sharing an interval
+    /// drift apart instead of polling in bursts. Never shorter than `poll_floor`.
+    fn jittered_interval(&self, channel_name: &str) -> std::time::Duration {
+        let interval = self
+            .channel_intervals
+            .get(channel_name)
+            .copied()
+            .unwrap_or(self.poll_interval);
+        if self.poll_jitter <= 0.0 {
+            return interval;
+        }
+        // RandomState is seeded per instance, which is plenty random for jitter
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        let factor = 1.0 + self.poll_jitter * (2.0 * random - 1.0);
+        interval.mul_f64(factor).max(self.poll_floor)
+    }
+
+    /// Re-reads `channels_file` if hot reload is enabled and the file changed.
+    /// Keeps the current list on any error.
+    fn reload_channels_file(&mut self) {
+        if !self.channels_file_reload {
+            return;
+        }
+        let Some(path) = self.channels_file.clone() else {
+            return;
         };
 
-        if !has_comments {
-            return Ok(());
+        let mtime = match std::fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(t) => t,
+            Err(e) => {
+                warn!("Cannot stat channels file {}: {}", path.display(), e);
+                return;
+            }
+        };
+        if self.channels_file_mtime == Some(mtime) {
+            return;
         }
 
-        // Get recent messages (posts) from the channel
-        let mut messages = self.client.iter_messages(peer_ref.clone()).limit(200);
+        match load_channels_file(&path) {
+            Ok(channels) => {
+                self.set_channels(&channels);
+                info!("Channels file changed, now monitoring: {:?}", self.channels);
+                self.channels_file_mtime = Some(mtime);
+            }
+            Err(e) => error!("Failed to reload channels file: {:#}", e),
+        }
+    }
 
-        let mut posts = Vec::new();
-        while let Some(msg) = timeout(std::time::Duration::from_secs(15), messages.next())
-            .await
-            .context("Timeout fetching messages")?
-            .context("Error fetching messages")?
-        {
-            posts.push(msg);
+    /// Sends the channel's new (and edited) comments; returns how many.
+    #[tracing::instrument(skip_all, fields(channel = channel_name))]
+    async fn poll_channel(&mut self, channel_name: &str, tx: &mpsc::Sender<RawComment>) -> Result<usize> {
+        let peer_ref = self.resolve_channel(channel_name).await?;
+
+        // Check once per channel where its comments live
+        let discussion = if let Some(cached) = self.channel_discussions.get(channel_name) {
+            cached.clone()
+        } else {
+            let discussion = self.check_discussion(peer_ref.clone()).await;
+            let has_comments = !matches!(discussion, Discussion::None);
+            info!(
+                "Channel @{}: comments enabled = {}, forum = {}",
+                channel_name,
+                has_comments,
+                matches!(discussion, Discussion::Forum { .. })
+            );
+            self.channel_discussions
+                .insert(channel_name.to_string(), discussion.clone());
+            let _ = self.channel_status_tx.send((channel_name.to_string(), has_comments)).await;
+            discussion
+        };
+
+        let threads = match discussion {
+            Discussion::None => return Ok(0),
+            Discussion::Threads => self.post_threads(channel_name, peer_ref.clone()).await?,
+            Discussion::Forum { group, posts } => {
+                let mut threads = if posts {
+                    self.post_threads(channel_name, peer_ref.clone()).await?
+                } else {
+                    Vec::new()
+                };
+                match self.topic_threads(group).await {
+                    Ok(topics) => threads.extend(topics),
+                    Err(e) => warn!("Cannot list forum topics of @{}: {:#}", channel_name, e),
+                }
+                threads
+            }
+        };
+
+        let admins = if self.exclude_admins {
+            self.admins_for(channel_name, peer_ref.clone()).await
+        } else {
+            HashSet::new()
+        };
+
+        if let Some(window) = self.edit_window {
+            let cutoff = Utc::now() - window;
+            self.edit_dates.retain(|_, comments| {
+                comments.retain(|_, (date, _)| *date > cutoff);
+                !comments.is_empty()
+            });
         }
 
-        for post in &posts {
-            let post_id = post.id();
+        // New and edited comments emitted this cycle, capped by `max_comments_per_cycle`
+        let mut emitted = 0usize;
 
-            let replies_result = timeout(
-                std::time::Duration::from_secs(5),
-                self.get_replies(peer_ref.clone(), post_id),
-            )
-            .await;
+        for thread in &threads {
+            if emitted >= self.max_comments_per_cycle {
+                info!(
+                    "@{}: reached {} comments this cycle, deferring the rest",
+                    channel_name, self.max_comments_per_cycle
+                );
+                break;
+            }
+            let post_id = thread.id;
+            let key = (channel_name.to_string(), thread.key());
 
+            let last_seen = self.seen.last_seen(channel_name, thread.key());
+            // Comments still open to edits are fetched again along with the new ones
+            let min_id = match self.edit_dates.get(&key).and_then(|c| c.keys().next()) {
+                Some(&oldest) => last_seen.min(oldest - 1),
+                None => last_seen,
+            };
+
+            // Nothing new in the thread since the last poll, and no comments to
+            // re-check for edits: skip the request
+            if min_id == last_seen && thread.last_reply_id.is_some_and(|id| id <= last_seen) {
+                self.polled_posts.insert(key);
+                // Still recorded, so the cursor lists every post in the window
+                self.seen.advance(channel_name, thread.key(), last_seen);
+                continue;
+            }
+
+            let replies_result = self.get_replies(thread.peer.clone(), post_id, min_id).await;
             let reply_messages_opt = match replies_result {
-                Ok(Ok(msgs)) => Some(msgs),
-                Ok(Err(e)) => {
+                Ok(msgs) => Some(msgs),
+                Err(e) => {
                     warn!("Error getting replies for post {} in {}: {:#}", post_id, channel_name, e);
                     None
                 }
-                Err(_) => {
-                    warn!("Timeout getting replies for post {} in {}", post_id, channel_name);
-                    None
-                }
             };
 
             if let Some(mut reply_messages) = reply_messages_opt {
-                let last_seen = self
-                    .seen
-                    .get(&(channel_name.to_string(), post_id))
-                    .copied()
-                    .unwrap_or(0);
-
                 let mut max_id = last_seen;
 
-                for (comment_id, author, username, phone, text, date) in reply_messages.drain(..) {
-                    if comment_id <= last_seen {
+                // Oldest first, so a capped cycle leaves the cursor before the deferred rest
+                reply_messages.sort_by_key(|r| r.id);
+
+                // (comment, whether it is an edit of one already sent)
+                let mut to_emit = Vec::new();
+                for reply in reply_messages.drain(..) {
+                    // Older comments are only re-fetched to catch edits
+                    let edited = reply.id <= last_seen;
+                    if edited && !self.edit_changed(&key, &reply) {
+                        continue;
+                    }
+                    if emitted + to_emit.len() >= self.max_comments_per_cycle {
+                        break;
+                    }
+                    max_id = max_id.max(reply.id);
+                    if reply.author_id != 0 && admins.contains(&reply.author_id) {
+                        continue;
+                    }
+                    if self.backfill_since.is_some_and(|since| reply.date < since) {
                         continue;
                     }
-                    max_id = max_id.max(comment_id);
+                    self.track_for_edits(&key, &reply);
+                    to_emit.push((reply, edited));
+                }
+
+                // Velocity = new comments emitted for the post this poll. The first
+                // time a post is polled its backlog is not a burst, so it starts at 0.
+                let new_count = to_emit.iter().filter(|(_, edited)| !edited).count();
+                let velocity = if self.track_comment_velocity && self.polled_posts.contains(&key) {
+                    new_count as u32
+                } else {
+                    0
+                };
+                self.polled_posts.insert(key);
+
+                for (reply, edited) in to_emit {
+                    emitted += 1;
+                    if edited {
+                        info!("@{}: comment {} was edited, re-analyzing", channel_name, reply.id);
+                    }
+                    let author_bio = self.author_bio(reply.author_id, reply.access_hash).await;
 
                     let comment = RawComment {
                         channel: channel_name.to_string(),
                         post_id,
-                        comment_id,
-                        author,
-                        username,
-                        phone,
-                        text,
-                        date,
+                        comment_id: reply.id,
+                        author_id: reply.author_id,
+                        author: reply.author,
+                        username: reply.username,
+                        phone_e164: reply
+                            .phone
+                            .as_deref()
+                            .and_then(|p| phone::normalize(p, self.default_country)),
+                        phone: reply.phone,
+                        text: reply.text,
+                        post_text: thread.text.clone(),
+                        date: reply.date,
+                        reactions: reply.reactions,
+                        comment_velocity: velocity,
+                        reply_to_comment_id: reply.reply_to_comment_id,
+                        author_bio,
+                        is_premium: reply.is_premium,
+                        is_verified: reply.is_verified,
+                        topic_id: thread.topic_id,
+                        edited,
+                        views: reply.views,
+                        forwards: reply.forwards,
+                        aggregated_comment_ids: Vec::new(),
                     };
 
                     if tx.send(comment).await.is_err() {
-                        return Ok(());
+                        return Ok(emitted);
+                    }
+                }
+
+                self.seen.advance(channel_name, thread.key(), max_id);
+            }
+        }
+
+        // Posts that fell out of the listing window won't be listed again
+        let window = self
+            .known_posts
+            .get(channel_name)
+            .filter(|_| self.backfill_since.is_none());
+        if let Some(known) = window {
+            self.seen
+                .retain(channel_name, |key| key < 0 || known.posts.contains_key(&key));
+            self.polled_posts.retain(|(channel, key)| {
+                channel != channel_name || *key < 0 || known.posts.contains_key(key)
+            });
+        }
+
+        let last_post_id = threads
+            .iter()
+            .filter(|t| t.topic_id.is_none())
+            .map(|t| t.id)
+            .max()
+            .unwrap_or(0);
+        self.save_cursor(channel_name, last_post_id, emitted < self.max_comments_per_cycle)
+            .await;
+
+        Ok(emitted)
+    }
+
+    /// Remembers an emitted comment's edit date while it can still be edited.
+    fn track_for_edits(&mut self, key: &(String, i32), reply: &Reply) {
+        let Some(window) = self.edit_window else {
+            return;
+        };
+        if reply.date > Utc::now() - window {
+            self.edit_dates
+                .entry(key.clone())
+                .or_default()
+                .insert(reply.id, (reply.date, reply.edit_date));
+        }
+    }
+
+    /// Whether an already emitted comment has been edited since it was emitted.
+    fn edit_changed(&self, key: &(String, i32), reply: &Reply) -> bool {
+        reply.edit_date.is_some()
+            && self
+                .edit_dates
+                .get(key)
+                .and_then(|comments| comments.get(&reply.id))
+                .is_some_and(|(_, edit_date)| *edit_date != reply.edit_date)
+    }
+
+    /// The channel's recent posts (up to 200, or back to `backfill_since`) as reply threads.
+    /// Once a channel has been listed, only newer posts are listed and the known
+    /// ones just have their reply counters refreshed, until `FULL_LISTING_INTERVAL`.
+    /// A listing cut short by a transient error is started over, up to
+    /// `POST_FETCH_RETRIES` times; a lost session fails right away.
+    async fn post_threads(&mut self, channel_name: &str, peer_ref: PeerRef) -> Result<Vec<Thread>> {
+        let incremental = self.backfill_since.is_none()
+            && self.known_posts.get(channel_name).is_some_and(|known| {
+                (Utc::now() - known.listed_at).to_std().unwrap_or_default() < FULL_LISTING_INTERVAL
+            });
+        let mut attempt = 0;
+        loop {
+            let listing = if incremental {
+                self.fetch_post_activity(channel_name, peer_ref.clone()).await
+            } else {
+                self.fetch_post_threads(peer_ref.clone(), 0).await
+            };
+            let error = match listing {
+                Ok(threads) => {
+                    if self.backfill_since.is_none() {
+                        self.remember_posts(channel_name, &threads, !incremental);
+                    }
+                    return Ok(threads);
+                }
+                Err(e) => e,
+            };
+            match classify(&format!("{:#}", error)) {
+                ErrorKind::AuthLost => {
+                    self.health.set_authorized(false);
+                    return Err(error.context("Telegram session is no longer authorized; restart to sign in again"));
+                }
+                ErrorKind::Transient if attempt < POST_FETCH_RETRIES => {
+                    attempt += 1;
+                    warn!(
+                        "Listing posts of @{} failed, retrying ({}/{}): {:#}",
+                        channel_name, attempt, POST_FETCH_RETRIES, error
+                    );
+                    if self.runner.is_finished() {
+                        self.reconnect().await?;
+                    } else {
+                        tokio::time::sleep(std::time::Duration::from_secs(1 << attempt)).await;
                     }
                 }
+                _ => return Err(error),
+            }
+        }
+    }
 
-                if max_id > last_seen {
-                    self.seen
-                        .insert((channel_name.to_string(), post_id), max_id);
+    /// Lists the channel's posts newest first, down to `min_id` (exclusive; 0 = no bound).
+    async fn fetch_post_threads(&self, peer_ref: PeerRef, min_id: i32) -> Result<Vec<Thread>> {
+        let limit = if self.backfill_since.is_some() { usize::MAX } else { RECENT_POSTS };
+        let mut threads = Vec::new();
+        let mut offset_id = 0;
+
+        loop {
+            let request = tl::functions::messages::GetHistory {
+                peer: peer_ref.clone().into(),
+                offset_id,
+                offset_date: 0,
+                add_offset: 0,
+                limit: HISTORY_PAGE_SIZE,
+                max_id: 0,
+                min_id,
+                hash: 0,
+            };
+            let (client, request) = (&self.client, &request);
+            let response = self
+                .retry_flood_wait("GetHistory", move || async move {
+                    timeout(std::time::Duration::from_secs(15), client.invoke(request))
+                        .await
+                        .context("Timeout fetching messages")?
+                        .map_err(anyhow::Error::from)
+                })
+                .await
+                .context("Error fetching messages")?;
+
+            let messages = match response {
+                tl::enums::messages::Messages::Messages(m) => m.messages,
+                tl::enums::messages::Messages::Slice(m) => m.messages,
+                tl::enums::messages::Messages::ChannelMessages(m) => m.messages,
+                _ => return Ok(threads),
+            };
+            let page_len = messages.len();
+
+            // Pages come newest first; the next one continues below the oldest
+            for message in messages {
+                let m = match message {
+                    tl::enums::Message::Message(m) => m,
+                    tl::enums::Message::Service(m) => {
+                        offset_id = m.id;
+                        continue;
+                    }
+                    tl::enums::Message::Empty(m) => {
+                        offset_id = m.id;
+                        continue;
+                    }
+                };
+                offset_id = m.id;
+                let date = DateTime::from_timestamp(m.date as i64, 0).unwrap_or_default();
+                if self.backfill_since.is_some_and(|since| date < since) {
+                    return Ok(threads);
                 }
+                let last_reply_id = m.replies.map(|r| match r {
+                    tl::enums::MessageReplies::Replies(r) => r.max_id.unwrap_or(0),
+                });
+                threads.push(Thread {
+                    peer: peer_ref.clone(),
+                    id: m.id,
+                    text: m.message,
+                    topic_id: None,
+                    last_reply_id,
+                });
+                if threads.len() >= limit {
+                    return Ok(threads);
+                }
+            }
+
+            if page_len < HISTORY_PAGE_SIZE as usize {
+                return Ok(threads);
             }
         }
+    }
 
-        Ok(())
+    /// Posts published since the channel's last listing, followed by its known
+    /// posts with their current reply counters. Known posts whose text isn't
+    /// held yet (resumed from the cursor) are fetched whole instead.
+    async fn fetch_post_activity(&self, channel_name: &str, peer_ref: PeerRef) -> Result<Vec<Thread>> {
+        let Some(known) = self.known_posts.get(channel_name) else {
+            return self.fetch_post_threads(peer_ref, 0).await;
+        };
+        let newest = known.posts.keys().next_back().copied().unwrap_or(0);
+        let mut threads = self.fetch_post_threads(peer_ref.clone(), newest).await?;
+
+        let (with_text, without_text): (Vec<i32>, Vec<i32>) =
+            known.posts.keys().rev().copied().partition(|id| known.posts[id].is_some());
+        let last_reply_ids = self.last_reply_ids(peer_ref.clone(), &with_text).await?;
+        for (id, last_reply_id) in with_text.into_iter().zip(last_reply_ids) {
+            threads.push(Thread {
+                peer: peer_ref.clone(),
+                id,
+                text: known.posts[&id].clone().unwrap_or_default(),
+                topic_id: None,
+                last_reply_id,
+            });
+        }
+        threads.extend(self.fetch_posts_by_id(peer_ref, &without_text).await?);
+        Ok(threads)
+    }
+
+    /// The given posts as reply threads, with their text and reply counters.
+    /// Deleted posts are left out.
+    async fn fetch_posts_by_id(&self, peer_ref: PeerRef, ids: &[i32]) -> Result<Vec<Thread>> {
+        let mut threads = Vec::with_capacity(ids.len());
+        if ids.is_empty() {
+            return Ok(threads);
+        }
+        let channel = input_channel(peer_ref.clone()).context("Channel is not a broadcast channel")?;
+        for chunk in ids.chunks(VIEWS_BATCH_SIZE) {
+            let request = tl::functions::channels::GetMessages {
+                channel: channel.clone(),
+                id: chunk
+                    .iter()
+                    .map(|&id| tl::enums::InputMessage::Id(tl::types::InputMessageId { id }))
+                    .collect(),
+            };
+            let (client, request) = (&self.client, &request);
+            let response = self
+                .retry_flood_wait("GetMessages", move || async move {
+                    timeout(std::time::Duration::from_secs(15), client.invoke(request))
+                        .await
+                        .context("Timeout fetching posts")?
+                        .map_err(anyhow::Error::from)
+                })
+                .await
+                .context("Error fetching posts")?;
+
+            let messages = match response {
+                tl::enums::messages::Messages::Messages(m) => m.messages,
+                tl::enums::messages::Messages::Slice(m) => m.messages,
+                tl::enums::messages::Messages::ChannelMessages(m) => m.messages,
+                _ => continue,
+            };
+            threads.extend(messages.into_iter().filter_map(|message| match message {
+                tl::enums::Message::Message(m) => Some(Thread {
+                    peer: peer_ref.clone(),
+                    id: m.id,
+                    text: m.message,
+                    topic_id: None,
+                    last_reply_id: m.replies.map(|r| match r {
+                        tl::enums::MessageReplies::Replies(r) => r.max_id.unwrap_or(0),
+                    }),
+                }),
+                _ => None,
+            }));
+        }
+        Ok(threads)
     }
 
-    async fn check_has_comments(&self, peer_ref: grammers_session::types::PeerRef) -> bool {
-        let input_peer: tl::enums::InputPeer = peer_ref.into();
-        let input_channel = match input_peer {
-            tl::enums::InputPeer::Channel(c) => {
-                tl::enums::InputChannel::Channel(tl::types::InputChannel {
-                    channel_id: c.channel_id,
-                    access_hash: c.access_hash,
+    /// Newest reply ID of each post, in the order given (see `Thread::last_reply_id`).
+    async fn last_reply_ids(&self, peer_ref: PeerRef, ids: &[i32]) -> Result<Vec<Option<i32>>> {
+        let mut last_reply_ids = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(VIEWS_BATCH_SIZE) {
+            let request = tl::functions::messages::GetMessagesViews {
+                peer: peer_ref.clone().into(),
+                id: chunk.to_vec(),
+                increment: false,
+            };
+            let (client, request) = (&self.client, &request);
+            let tl::enums::messages::MessageViews::Views(views) = self
+                .retry_flood_wait("GetMessagesViews", move || async move {
+                    timeout(std::time::Duration::from_secs(15), client.invoke(request))
+                        .await
+                        .context("Timeout fetching reply counters")?
+                        .map_err(anyhow::Error::from)
                 })
+                .await
+                .context("Error fetching reply counters")?;
+            if views.views.len() != chunk.len() {
+                anyhow::bail!(
+                    "GetMessagesViews returned {} entries for {} posts",
+                    views.views.len(),
+                    chunk.len()
+                );
             }
-            _ => return false,
+            last_reply_ids.extend(views.views.into_iter().map(|v| match v {
+                tl::enums::MessageViews::Views(v) => v.replies.map(|r| match r {
+                    tl::enums::MessageReplies::Replies(r) => r.max_id.unwrap_or(0),
+                }),
+            }));
+        }
+        Ok(last_reply_ids)
+    }
+
+    /// Records the listed posts as the channel's known ones; a full listing replaces them.
+    fn remember_posts(&mut self, channel_name: &str, threads: &[Thread], full: bool) {
+        if full {
+            self.known_posts.insert(
+                channel_name.to_string(),
+                KnownPosts {
+                    posts: BTreeMap::new(),
+                    listed_at: Utc::now(),
+                },
+            );
+        }
+        let Some(known) = self.known_posts.get_mut(channel_name) else {
+            return;
         };
+        for thread in threads {
+            known.posts.insert(thread.id, Some(thread.text.clone()));
+        }
+        while known.posts.len() > RECENT_POSTS {
+            known.posts.pop_first();
+        }
+    }
 
-        let request = tl::functions::channels::GetFullChannel { channel: input_channel };
+    /// The forum group's topics (most recently active first) as reply threads.
+    /// The General topic has no root message to reply to, so it is left out.
+    async fn topic_threads(&self, group: PeerRef) -> Result<Vec<Thread>> {
+        let channel = input_channel(group.clone()).context("Forum group is not a channel")?;
+        let request = tl::functions::channels::GetForumTopics {
+            channel,
+            q: None,
+            offset_date: 0,
+            offset_id: 0,
+            offset_topic: 0,
+            limit: FORUM_TOPICS_LIMIT,
+        };
+        let (client, request) = (&self.client, &request);
+        let tl::enums::messages::ForumTopics::Topics(topics) = self
+            .retry_flood_wait("GetForumTopics", move || async move {
+                timeout(std::time::Duration::from_secs(10), client.invoke(request))
+                    .await
+                    .context("GetForumTopics timeout")?
+                    .map_err(anyhow::Error::from)
+            })
+            .await?;
 
-        match timeout(std::time::Duration::from_secs(10), self.client.invoke(&request)).await {
-            Ok(Ok(tl::enums::messages::ChatFull::Full(full))) => match full.full_chat {
-                tl::enums::ChatFull::ChannelFull(cf) => cf.linked_chat_id.is_some(),
-                _ => false,
+        Ok(topics
+            .topics
+            .into_iter()
+            .filter_map(|topic| match topic {
+                tl::enums::ForumTopic::Topic(t) if t.id != GENERAL_TOPIC_ID => Some(Thread {
+                    peer: group.clone(),
+                    id: t.id,
+                    text: t.title,
+                    topic_id: Some(t.id),
+                    last_reply_id: Some(t.top_message),
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Updates the channel's entry in the poll cursor from `seen` and writes it out.
+    async fn save_cursor(&mut self, channel_name: &str, last_post_id: i32, caught_up: bool) {
+        let Some(path) = &self.cursor_file else {
+            return;
+        };
+
+        let posts = self.seen.channel(channel_name);
+        let previous_post_id = self
+            .cursor
+            .channels
+            .get(channel_name)
+            .map_or(0, |c| c.last_post_id);
+        self.cursor.channels.insert(
+            channel_name.to_string(),
+            ChannelCursor {
+                last_post_id: last_post_id.max(previous_post_id),
+                posts,
+                caught_up,
+                listed_at: self.known_posts.get(channel_name).map(|known| known.listed_at),
+                updated_at: Utc::now(),
             },
-            Ok(Err(e)) => {
-                warn!("GetFullChannel error: {:#}", e);
-                false
+        );
+
+        if let Err(e) = self.cursor.save(path).await {
+            error!("Failed to save poll cursor: {:#}", e);
+        }
+    }
+
+    /// Resolves a `channels` entry (username, numeric ID or invite link) to a peer.
+    /// IDs and invite links are resolved once and cached; invite links are joined.
+    async fn resolve_channel(&mut self, entry: &str) -> Result<PeerRef> {
+        if let Some(peer_ref) = self.resolved_peers.get(entry) {
+            return Ok(peer_ref.clone());
+        }
+
+        let peer_ref = match ChannelRef::parse(entry) {
+            ChannelRef::Username(username) => {
+                let channel = timeout(
+                    std::time::Duration::from_secs(15),
+                    self.client.resolve_username(&username),
+                )
+                .await
+                .context("Timeout resolving channel username")?
+                .context(format!("Channel @{} not found", username))?
+                .context(format!("Channel @{} not found", username))?;
+
+                // Usernames can change hands, so they are re-resolved on every poll
+                return timeout(std::time::Duration::from_secs(10), channel.to_ref())
+                    .await
+                    .context("Timeout getting peer ref")?
+                    .context("Cannot get peer ref for channel");
+            }
+            ChannelRef::Id(id) => self.find_joined_channel(id).await?,
+            ChannelRef::Invite(hash) => self.join_by_invite(&hash).await?,
+        };
+
+        self.resolved_peers.insert(entry.to_string(), peer_ref.clone());
+        Ok(peer_ref)
+    }
+
+    /// Looks a channel ID up among the account's dialogs (the access hash is needed
+    /// to address it, and is only known for channels we've joined).
+    async fn find_joined_channel(&self, id: i64) -> Result<PeerRef> {
+        let mut offset_date = 0;
+        for _ in 0..DIALOG_PAGES {
+            let request = tl::functions::messages::GetDialogs {
+                exclude_pinned: false,
+                folder_id: None,
+                offset_date,
+                offset_id: 0,
+                offset_peer: tl::enums::InputPeer::Empty,
+                limit: 100,
+                hash: 0,
+            };
+            let response = timeout(std::time::Duration::from_secs(15), self.client.invoke(&request))
+                .await
+                .context("Timeout listing dialogs")??;
+
+            let (chats, messages) = match response {
+                tl::enums::messages::Dialogs::Dialogs(d) => (d.chats, Vec::new()),
+                tl::enums::messages::Dialogs::Slice(d) => (d.chats, d.messages),
+                tl::enums::messages::Dialogs::NotModified(_) => break,
+            };
+            if let Some(peer_ref) = channel_in(&chats, Some(id)) {
+                return Ok(peer_ref);
             }
-            Err(_) => {
-                warn!("GetFullChannel timeout");
-                false
+
+            let oldest = messages
+                .iter()
+                .filter_map(|m| match m {
+                    tl::enums::Message::Message(m) => Some(m.date),
+                    tl::enums::Message::Service(m) => Some(m.date),
+                    tl::enums::Message::Empty(_) => None,
+                })
+                .min();
+            match oldest {
+                Some(date) if date != offset_date => offset_date = date,
+                _ => break,
             }
         }
+
+        anyhow::bail!("Channel {} not found among joined chats", id)
     }
 
-    async fn get_replies(
-        &self,
-        peer_ref: grammers_session::types::PeerRef,
-        post_id: i32,
-    ) -> Result<Vec<(i32, String, Option<String>, Option<String>, String, DateTime<Utc>)>> {
-        let input_peer: tl::enums::InputPeer = peer_ref.clone().into();
+    /// Joins a channel by invite hash, tolerating an existing membership.
+    async fn join_by_invite(&self, hash: &str) -> Result<PeerRef> {
+        let import = tl::functions::messages::ImportChatInvite { hash: hash.to_string() };
+        let chats = match self.client.invoke(&import).await {
+            Ok(tl::enums::Updates::Updates(u)) => u.chats,
+            Ok(tl::enums::Updates::Combined(u)) => u.chats,
+            Ok(_) => Vec::new(),
+            Err(e) if e.to_string().contains("USER_ALREADY_PARTICIPANT") => Vec::new(),
+            Err(e) => return Err(anyhow::Error::from(e).context("Failed to join by invite link")),
+        };
+        if let Some(peer_ref) = channel_in(&chats, None) {
+            info!("Joined channel by invite link");
+            return Ok(peer_ref);
+        }
 
-        let request = tl::functions::messages::GetReplies {
-            peer: input_peer,
-            msg_id: post_id,
-            offset_id: 0,
-            offset_date: 0,
-            add_offset: 0,
-            limit: 50,
-            max_id: 0,
-            min_id: 0,
-            hash: 0,
+        // Already a member (or the join response had no chat): ask what the link points to
+        let check = tl::functions::messages::CheckChatInvite { hash: hash.to_string() };
+        let chat = match self.client.invoke(&check).await.context("Failed to check invite link")? {
+            tl::enums::ChatInvite::Already(c) => c.chat,
+            tl::enums::ChatInvite::Peek(c) => c.chat,
+            tl::enums::ChatInvite::Invite(_) => anyhow::bail!("Invite link did not grant access"),
         };
+        channel_in(&[chat], None).context("Invite link does not point to a channel")
+    }
 
-        let response = match self.client.invoke(&request).await {
-            Ok(r) => r,
+    /// Returns the cached admin set for a channel, refreshing it once the TTL expires.
+    /// On fetch failure the previous set (or an empty one) is kept until the next TTL.
+    async fn admins_for(
+        &mut self,
+        channel_name: &str,
+        peer_ref: PeerRef,
+    ) -> HashSet<i64> {
+        if let Some((fetched_at, admins)) = self.channel_admins.get(channel_name) {
+            if fetched_at.elapsed() < self.admin_cache_ttl {
+                return admins.clone();
+            }
+        }
+
+        let admins = match self.fetch_admins(peer_ref).await {
+            Ok(admins) => {
+                info!("Channel @{}: {} admins excluded from analysis", channel_name, admins.len());
+                admins
+            }
             Err(e) => {
-                let msg = e.to_string();
-                if msg.contains("MSG_ID_INVALID") || msg.contains("CHANNEL_PRIVATE") {
-                    return Ok(vec![]);
+                warn!("Cannot fetch admins for @{}: {:#}", channel_name, e);
+                self.channel_admins
+                    .get(channel_name)
+                    .map(|(_, a)| a.clone())
+                    .unwrap_or_default()
+            }
+        };
+
+        self.channel_admins
+            .insert(channel_name.to_string(), (Instant::now(), admins.clone()));
+        admins
+    }
+
+    /// Fetches admin user IDs of the channel's linked discussion group, plus the
+    /// channel's own admins when visible (usually requires admin rights; ignored if not).
+    async fn fetch_admins(&self, peer_ref: PeerRef) -> Result<HashSet<i64>> {
+        let channel = input_channel(peer_ref).context("Peer is not a channel")?;
+
+        let request = tl::functions::channels::GetFullChannel { channel: channel.clone() };
+        let tl::enums::messages::ChatFull::Full(full) =
+            timeout(std::time::Duration::from_secs(10), self.client.invoke(&request))
+                .await
+                .context("Timeout getting full channel")??;
+
+        let linked_chat_id = match &full.full_chat {
+            tl::enums::ChatFull::ChannelFull(cf) => cf.linked_chat_id,
+            _ => None,
+        };
+        let linked = linked_chat_id.and_then(|id| {
+            full.chats.iter().find_map(|chat| match chat {
+                tl::enums::Chat::Channel(c) if c.id == id => {
+                    Some(tl::enums::InputChannel::Channel(tl::types::InputChannel {
+                        channel_id: c.id,
+                        access_hash: c.access_hash.unwrap_or(0),
+                    }))
                 }
-                return Err(e.into());
+                _ => None,
+            })
+        });
+
+        let mut admins = HashSet::new();
+        if let Some(group) = linked {
+            admins.extend(self.get_admin_ids(group).await?);
+        }
+        if let Ok(ids) = self.get_admin_ids(channel).await {
+            admins.extend(ids);
+        }
+        Ok(admins)
+    }
+
+    async fn get_admin_ids(&self, channel: tl::enums::InputChannel) -> Result<Vec<i64>> {
+        let request = tl::functions::channels::GetParticipants {
+            channel,
+            filter: tl::enums::ChannelParticipantsFilter::ChannelParticipantsAdmins,
+            offset: 0,
+            limit: 200,
+            hash: 0,
+        };
+
+        let response = timeout(std::time::Duration::from_secs(10), self.client.invoke(&request))
+            .await
+            .context("Timeout getting admins")??;
+
+        let participants = match response {
+            tl::enums::channels::ChannelParticipants::Participants(p) => p.participants,
+            tl::enums::channels::ChannelParticipants::NotModified => return Ok(vec![]),
+        };
+
+        Ok(participants
+            .iter()
+            .filter_map(|p| match p {
+                tl::enums::ChannelParticipant::Creator(c) => Some(c.user_id),
+                tl::enums::ChannelParticipant::Admin(a) => Some(a.user_id),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Finds where the channel's comments live: its linked discussion group,
+    /// possibly a forum, or the chat's own topics when it is a forum itself.
+    async fn check_discussion(&self, peer_ref: PeerRef) -> Discussion {
+        let Some(input_channel) = input_channel(peer_ref.clone()) else {
+            return Discussion::None;
+        };
+
+        let request = tl::functions::channels::GetFullChannel { channel: input_channel };
+        let (client, request) = (&self.client, &request);
+        let result = self
+            .retry_flood_wait("GetFullChannel", move || async move {
+                timeout(std::time::Duration::from_secs(10), client.invoke(request))
+                    .await
+                    .context("GetFullChannel timeout")?
+                    .map_err(anyhow::Error::from)
+            })
+            .await;
+
+        let full = match result {
+            Ok(tl::enums::messages::ChatFull::Full(full)) => full,
+            Err(e) => {
+                warn!("GetFullChannel error: {:#}", e);
+                return Discussion::None;
             }
         };
+        let tl::enums::ChatFull::ChannelFull(cf) = &full.full_chat else {
+            return Discussion::None;
+        };
 
-        let mut results = Vec::new();
+        let is_forum = |id: i64| {
+            full.chats
+                .iter()
+                .any(|chat| matches!(chat, tl::enums::Chat::Channel(c) if c.id == id && c.forum))
+        };
+        if is_forum(cf.id) {
+            return Discussion::Forum { group: peer_ref, posts: false };
+        }
+        match cf.linked_chat_id {
+            Some(id) if is_forum(id) => match channel_in(&full.chats, Some(id)) {
+                Some(group) => Discussion::Forum { group, posts: true },
+                None => Discussion::Threads,
+            },
+            Some(_) => Discussion::Threads,
+            None => Discussion::None,
+        }
+    }
 
-        match response {
-            tl::enums::messages::Messages::Messages(msgs) => {
-                Self::extract_comments(&msgs.messages, &msgs.users, &mut results);
+    /// The author's profile bio, cached for `bio_cache_ttl`. Once this cycle's
+    /// lookups are used up, uncached authors get None (or their expired bio).
+    async fn author_bio(&mut self, user_id: i64, access_hash: Option<i64>) -> Option<String> {
+        if !self.fetch_author_bios || user_id == 0 {
+            return None;
+        }
+        let stale = match self.author_bios.get(&user_id) {
+            Some((fetched_at, bio)) if fetched_at.elapsed() < self.bio_cache_ttl => return bio.clone(),
+            Some((_, bio)) => bio.clone(),
+            None => None,
+        };
+        let Some(access_hash) = access_hash else {
+            return stale;
+        };
+        if self.bio_lookups_left == 0 {
+            return stale;
+        }
+        self.bio_lookups_left -= 1;
+
+        tokio::time::sleep(BIO_LOOKUP_SPACING).await;
+        let bio = match self.fetch_bio(user_id, access_hash).await {
+            Ok(bio) => bio,
+            Err(e) => {
+                // Cached anyway, so a failing user isn't retried every cycle
+                warn!("Cannot fetch bio of user {}: {:#}", user_id, e);
+                stale
             }
-            tl::enums::messages::Messages::Slice(msgs) => {
-                Self::extract_comments(&msgs.messages, &msgs.users, &mut results);
+        };
+        self.author_bios.insert(user_id, (Instant::now(), bio.clone()));
+        bio
+    }
+
+    async fn fetch_bio(&self, user_id: i64, access_hash: i64) -> Result<Option<String>> {
+        let request = tl::functions::users::GetFullUser {
+            id: tl::enums::InputUser::User(tl::types::InputUser { user_id, access_hash }),
+        };
+        let (client, request) = (&self.client, &request);
+        let tl::enums::users::UserFull::Full(full) = self
+            .retry_flood_wait("GetFullUser", move || async move {
+                timeout(std::time::Duration::from_secs(10), client.invoke(request))
+                    .await
+                    .context("GetFullUser timeout")?
+                    .map_err(anyhow::Error::from)
+            })
+            .await?;
+        let tl::enums::UserFull::Full(user) = full.full_user;
+        Ok(user.about.filter(|about| !about.trim().is_empty()))
+    }
+
+    /// Runs `op`, and on FLOOD_WAIT sleeps for the time Telegram asks before
+    /// retrying it, up to `flood_wait_retries` times. Other errors pass through.
+    async fn retry_flood_wait<T, F, Fut>(&self, what: &str, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.flood_wait_retries => {
+                    let Some(secs) = flood_wait_secs(&format!("{:#}", e)) else {
+                        return Err(e);
+                    };
+                    attempt += 1;
+                    warn!(
+                        "FLOOD_WAIT on {}, sleeping {}s (retry {}/{})",
+                        what, secs, attempt, self.flood_wait_retries
+                    );
+                    tokio::time::sleep(std::time::Duration::from_secs(secs)).await;
+                }
+                result => return result,
             }
-            tl::enums::messages::Messages::ChannelMessages(msgs) => {
-                Self::extract_comments(&msgs.messages, &msgs.users, &mut results);
+        }
+    }
+
+    /// Fetches the replies to `post_id` newer than `min_id`, oldest page first,
+    /// until a short page comes back or `max_replies_per_post` is reached. A capped
+    /// fetch thus stops at the newest replies, which the next poll picks up once
+    /// `seen` has advanced past the ones returned. Replies come back unordered;
+    /// the caller sorts them oldest-first.
+    #[tracing::instrument(skip(self, peer_ref))]
+    async fn get_replies(
+        &self,
+        peer_ref: PeerRef,
+        post_id: i32,
+        min_id: i32,
+    ) -> Result<Vec<Reply>> {
+        let mut results = Vec::new();
+        // Newest reply fetched so far; each page starts right above it
+        let mut after = min_id;
+
+        while results.len() < self.max_replies_per_post {
+            // A negative add_offset turns the page around: the replies from
+            // offset_id upwards. min_id stays 0, as Telegram may return nothing
+            // for a low one; replies at or below `after` are dropped below instead.
+            let request = tl::functions::messages::GetReplies {
+                peer: peer_ref.clone().into(),
+                msg_id: post_id,
+                offset_id: after + 1,
+                offset_date: 0,
+                add_offset: -REPLIES_PAGE_SIZE,
+                limit: REPLIES_PAGE_SIZE,
+                max_id: 0,
+                min_id: 0,
+                hash: 0,
+            };
+
+            let (client, request) = (&self.client, &request);
+            let response = self
+                .retry_flood_wait("GetReplies", move || async move {
+                    timeout(std::time::Duration::from_secs(5), client.invoke(request))
+                        .await
+                        .context("Timeout getting replies")?
+                        .map_err(anyhow::Error::from)
+                })
+                .await;
+            let response = match response {
+                Ok(r) => r,
+                Err(e) => {
+                    let msg = e.to_string();
+                    if msg.contains("MSG_ID_INVALID") || msg.contains("CHANNEL_PRIVATE") {
+                        return Ok(vec![]);
+                    }
+                    return Err(e);
+                }
+            };
+
+            let (messages, users) = match response {
+                tl::enums::messages::Messages::Messages(msgs) => (msgs.messages, msgs.users),
+                tl::enums::messages::Messages::Slice(msgs) => (msgs.messages, msgs.users),
+                tl::enums::messages::Messages::ChannelMessages(msgs) => (msgs.messages, msgs.users),
+                _ => return Ok(results),
+            };
+            let page_len = messages.len();
+            let message_id = |m: &tl::enums::Message| match m {
+                tl::enums::Message::Empty(m) => m.id,
+                tl::enums::Message::Message(m) => m.id,
+                tl::enums::Message::Service(m) => m.id,
+            };
+            let newest = messages.iter().map(message_id).max();
+            let messages: Vec<_> = messages.into_iter().filter(|m| message_id(m) > after).collect();
+            self.extract_comments(&messages, &users, &mut results);
+
+            // Continue above the newest message of this page
+            match newest {
+                Some(id) if page_len >= REPLIES_PAGE_SIZE as usize && id > after => after = id,
+                _ => return Ok(results),
             }
-            _ => {}
         }
 
+        warn!(
+            "Post {}: more than {} new replies, the newer ones wait for the next poll",
+            post_id, self.max_replies_per_post
+        );
         Ok(results)
     }
 
     fn extract_comments(
+        &self,
         messages: &[tl::enums::Message],
         users: &[tl::enums::User],
-        results: &mut Vec<(i32, String, Option<String>, Option<String>, String, DateTime<Utc>)>,
+        results: &mut Vec<Reply>,
     ) {
         struct UserInfo {
             name: String,
+            access_hash: Option<i64>,
             username: Option<String>,
             phone: Option<String>,
+            is_premium: bool,
+            is_verified: bool,
         }
 
         let user_map: HashMap<i64, UserInfo> = users
@@ -313,9 +1656,18 @@ impl TelegramScraper {
                         .as_deref()
                         .unwrap_or("Unknown")
                         .to_string();
-                    let username = user.username.clone();
-                    let phone = user.phone.clone();
-                    Some((user.id, UserInfo { name, username, phone }))
+                    let access_hash = if user.min { None } else { user.access_hash };
+                    let username = user.username.clone().filter(|_| self.capture_username);
+                    let phone = user.phone.clone().filter(|_| self.capture_phone);
+                    let info = UserInfo {
+                        name,
+                        access_hash,
+                        username,
+                        phone,
+                        is_premium: user.premium,
+                        is_verified: user.verified,
+                    };
+                    Some((user.id, info))
                 }
                 _ => None,
             })
@@ -336,14 +1688,87 @@ impl TelegramScraper {
                 let author = info
                     .map(|i| i.name.clone())
                     .unwrap_or_else(|| "Anonymous".to_string());
+                let access_hash = info.and_then(|i| i.access_hash);
                 let username = info.and_then(|i| i.username.clone());
                 let phone = info.and_then(|i| i.phone.clone());
+                let is_premium = info.is_some_and(|i| i.is_premium);
+                let is_verified = info.is_some_and(|i| i.is_verified);
 
                 let date = DateTime::from_timestamp(m.date as i64, 0)
                     .unwrap_or_default();
 
-                results.push((m.id, author, username, phone, text, date));
+                let reactions = match &m.reactions {
+                    Some(tl::enums::MessageReactions::Reactions(r)) => r
+                        .results
+                        .iter()
+                        .filter_map(|rc| match rc {
+                            tl::enums::ReactionCount::Count(c) => {
+                                let emoji = match &c.reaction {
+                                    tl::enums::Reaction::Emoji(e) => e.emoticon.clone(),
+                                    tl::enums::Reaction::CustomEmoji(e) => {
+                                        format!("custom:{}", e.document_id)
+                                    }
+                                    tl::enums::Reaction::Paid => "⭐".to_string(),
+                                    tl::enums::Reaction::Empty => return None,
+                                };
+                                Some((emoji, c.count))
+                            }
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+
+                // Inside a thread every comment replies to something: the root (the
+                // post's copy in the discussion group) or, with a top ID set, another comment
+                let reply_to_comment_id = match &m.reply_to {
+                    Some(tl::enums::MessageReplyHeader::Header(h)) => match h.reply_to_top_id {
+                        Some(top) if h.reply_to_msg_id != Some(top) => h.reply_to_msg_id,
+                        _ => None,
+                    },
+                    _ => None,
+                };
+
+                results.push(Reply {
+                    id: m.id,
+                    author_id,
+                    access_hash,
+                    author,
+                    username,
+                    phone,
+                    is_premium,
+                    is_verified,
+                    text,
+                    date,
+                    edit_date: m.edit_date,
+                    views: m.views,
+                    forwards: m.forwards,
+                    reactions,
+                    reply_to_comment_id,
+                });
             }
         }
     }
 }
+
+/// The peer as an input channel, if it is a channel or supergroup.
+fn input_channel(peer_ref: PeerRef) -> Option<tl::enums::InputChannel> {
+    let input_peer: tl::enums::InputPeer = peer_ref.into();
+    match input_peer {
+        tl::enums::InputPeer::Channel(c) => Some(tl::enums::InputChannel::Channel(tl::types::InputChannel {
+            channel_id: c.channel_id,
+            access_hash: c.access_hash,
+        })),
+        _ => None,
+    }
+}
+
+/// First channel in `chats` (with the given ID, if any) as a peer reference.
+fn channel_in(chats: &[tl::enums::Chat], id: Option<i64>) -> Option<PeerRef> {
+    chats.iter().find_map(|chat| match chat {
+        tl::enums::Chat::Channel(c) if id.is_none_or(|id| c.id == id) => Some(PeerRef {
+            id: PeerId::channel(c.id),
+            auth: PeerAuth::from_hash(c.access_hash.unwrap_or(0)),
+        }),
+        _ => None,
+    })
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::warn;
+
+use crate::storage::write_atomic;
+
+/// Durable poll position, so a restart resumes where the previous run stopped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PollCursor {
+    pub channels: HashMap<String, ChannelCursor>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChannelCursor {
+    /// Highest post ID seen in the channel
+    #[serde(default)]
+    pub last_post_id: i32,
+    /// post_id → highest comment ID already emitted, for every post in the
+    /// listing window (0 = none yet); forum topics are keyed by their negated ID
+    pub posts: HashMap<i32, i32>,
+    /// The last poll emitted everything it found (was not cut short by
+    /// `max_comments_per_cycle`)
+    #[serde(default)]
+    pub caught_up: bool,
+    /// Last full post listing, so a restart keeps the full-listing cadence
+    #[serde(default)]
+    pub listed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PollCursor {
+    /// Loads the cursor, starting fresh if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        let loaded = std::fs::read_to_string(path)
+            .context("Failed to read cursor file")
+            .and_then(|text| serde_json::from_str(&text).context("Failed to parse cursor file"));
+        match loaded {
+            Ok(cursor) => cursor,
+            Err(e) => {
+                warn!("Ignoring poll cursor {}: {:#}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the cursor atomically (temp file + rename).
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(self).context("Failed to serialize poll cursor")?;
+        write_atomic(path, json.as_bytes()).await
+    }
+}
+
+/// Highest emitted comment ID per thread, held separately for each channel. A
+/// channel's poll only reads and advances its own map, and only ever forward,
+/// so a poll that fails halfway leaves every channel's dedup state valid.
+#[derive(Debug, Default)]
+pub struct SeenComments {
+    channels: HashMap<String, HashMap<i32, i32>>,
+}
+
+impl SeenComments {
+    /// Highest comment ID already emitted in `thread` of `channel` (0 if none).
+    pub fn last_seen(&self, channel: &str, thread: i32) -> i32 {
+        self.channels
+            .get(channel)
+            .and_then(|threads| threads.get(&thread))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Records comments up to `comment_id` in `thread` as emitted. Never moves back.
+    pub fn advance(&mut self, channel: &str, thread: i32, comment_id: i32) {
+        let last = self
+            .channels
+            .entry(channel.to_string())
+            .or_default()
+            .entry(thread)
+            .or_insert(0);
+        *last = (*last).max(comment_id);
+    }
+
+    /// Drops the channel's threads for which `keep` returns false.
+    pub fn retain(&mut self, channel: &str, mut keep: impl FnMut(i32) -> bool) {
+        if let Some(threads) = self.channels.get_mut(channel) {
+            threads.retain(|thread, _| keep(*thread));
+        }
+    }
+
+    /// thread → highest emitted comment ID, for the channel's cursor entry.
+    pub fn channel(&self, channel: &str) -> HashMap<i32, i32> {
+        self.channels.get(channel).cloned().unwrap_or_default()
+    }
+
+    /// Number of threads tracked across all channels.
+    pub fn len(&self) -> usize {
+        self.channels.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<&PollCursor> for SeenComments {
+    fn from(cursor: &PollCursor) -> Self {
+        let channels = cursor
+            .channels
+            .iter()
+            .map(|(channel, c)| (channel.clone(), c.posts.clone()))
+            .collect();
+        Self { channels }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels_sharing_a_thread_key_are_tracked_apart() {
+        let mut seen = SeenComments::default();
+        seen.advance("a", 7, 12);
+        seen.advance("b", 7, 30);
+
+        assert_eq!(seen.last_seen("a", 7), 12);
+        assert_eq!(seen.last_seen("b", 7), 30);
+        assert_eq!(seen.last_seen("c", 7), 0);
+        assert_eq!(seen.channel("a"), HashMap::from([(7, 12)]));
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn failing_channel_leaves_other_channels_deduplicated() {
+        let mut seen = SeenComments::default();
+        seen.advance("healthy", 7, 20);
+        seen.advance("broken", 7, 20);
+
+        // The broken channel's poll gets partway through its thread, then fails
+        seen.advance("broken", 7, 35);
+        assert_eq!(seen.last_seen("broken", 7), 35);
+        assert_eq!(seen.last_seen("healthy", 7), 20);
+
+        seen.advance("healthy", 7, 40);
+        assert_eq!(seen.last_seen("healthy", 7), 40);
+        assert_eq!(seen.last_seen("broken", 7), 35);
+
+        // Never moves back, whichever channel is advanced
+        seen.advance("healthy", 7, 3);
+        seen.advance("broken", 7, 3);
+        assert_eq!(seen.last_seen("healthy", 7), 40);
+        assert_eq!(seen.last_seen("broken", 7), 35);
+    }
+
+    #[test]
+    fn retain_prunes_only_the_given_channel() {
+        let mut seen = SeenComments::default();
+        for thread in [-2, 5, 9] {
+            seen.advance("a", thread, 1);
+            seen.advance("b", thread, 1);
+        }
+
+        seen.retain("a", |thread| thread < 0 || thread > 5);
+
+        assert_eq!(seen.channel("a").len(), 2);
+        assert_eq!(seen.last_seen("a", 5), 0);
+        assert_eq!(seen.channel("b").len(), 3);
+    }
+
+    #[test]
+    fn resumes_from_the_cursor_per_channel() {
+        let cursor: PollCursor = serde_json::from_str(
+            r#"{"channels": {
+                "a": {"posts": {"7": 12}, "updated_at": "2026-01-01T00:00:00Z"},
+                "b": {"last_post_id": 40, "posts": {"7": 30}, "caught_up": true,
+                      "updated_at": "2026-01-01T00:00:00Z"}
+            }}"#,
+        )
+        .unwrap();
+        let seen = SeenComments::from(&cursor);
+
+        assert_eq!(seen.last_seen("a", 7), 12);
+        assert_eq!(seen.last_seen("b", 7), 30);
+        // Written before last_post_id and caught_up existed
+        assert!(!cursor.channels["a"].caught_up);
+        assert_eq!(cursor.channels["a"].last_post_id, 0);
+    }
+}
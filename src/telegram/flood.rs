@@ -0,0 +1,100 @@
+/// Seconds Telegram asks us to wait, if `error` is a FLOOD_WAIT.
+///
+/// Accepts both the raw `FLOOD_WAIT_30` form and grammers' rendering of it,
+/// `rpc error 420: FLOOD_WAIT caused by ... (value: 30)`.
+pub fn flood_wait_secs(error: &str) -> Option<u64> {
+    let rest = &error[error.find("FLOOD_WAIT")? + "FLOOD_WAIT".len()..];
+
+    let digits = match rest.strip_prefix('_') {
+        Some(raw) => raw,
+        None => &rest[rest.find("value: ")? + "value: ".len()..],
+    };
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+/// How the scraper should react to a failed Telegram request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// FLOOD_WAIT: sleep this many seconds, then retry
+    FloodWait(u64),
+    /// The session was revoked or expired; retrying can't help
+    AuthLost,
+    /// Timeouts, dropped connections and Telegram-side 5xx: retry after a
+    /// pause, reconnecting first if the connection is gone
+    Transient,
+    /// Anything else (bad request, no access, ...): give up on this request
+    Other,
+}
+
+const AUTH_LOST: &[&str] = &[
+    "AUTH_KEY_UNREGISTERED",
+    "AUTH_KEY_INVALID",
+    "AUTH_KEY_DUPLICATED",
+    "SESSION_REVOKED",
+    "SESSION_EXPIRED",
+    "USER_DEACTIVATED",
+    "rpc error 401",
+];
+
+const TRANSIENT: &[&str] = &[
+    "timeout",
+    "timed out",
+    "dropped",
+    "io failed",
+    "read error",
+    "connection",
+    "rpc error 500",
+    "rpc error -503",
+];
+
+/// Classifies an error by its rendered message (`{:#}`, so context is included).
+pub fn classify(error: &str) -> ErrorKind {
+    if let Some(secs) = flood_wait_secs(error) {
+        return ErrorKind::FloodWait(secs);
+    }
+    if AUTH_LOST.iter().any(|marker| error.contains(marker)) {
+        return ErrorKind::AuthLost;
+    }
+    let lower = error.to_lowercase();
+    if TRANSIENT.iter().any(|marker| lower.contains(marker)) {
+        return ErrorKind::Transient;
+    }
+    ErrorKind::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_raw_form() {
+        assert_eq!(flood_wait_secs("FLOOD_WAIT_42"), Some(42));
+    }
+
+    #[test]
+    fn parses_grammers_form() {
+        let error = "rpc error 420: FLOOD_WAIT caused by messages.getReplies (value: 17)";
+        assert_eq!(flood_wait_secs(error), Some(17));
+    }
+
+    #[test]
+    fn classifies_errors() {
+        assert_eq!(classify("FLOOD_WAIT_5"), ErrorKind::FloodWait(5));
+        assert_eq!(
+            classify("Error fetching messages: rpc error 401: AUTH_KEY_UNREGISTERED caused by messages.getHistory"),
+            ErrorKind::AuthLost
+        );
+        assert_eq!(classify("Timeout fetching messages: deadline has elapsed"), ErrorKind::Transient);
+        assert_eq!(classify("Error fetching messages: request error: request dropped"), ErrorKind::Transient);
+        assert_eq!(classify("rpc error 400: CHANNEL_INVALID"), ErrorKind::Other);
+    }
+
+    #[test]
+    fn ignores_other_errors() {
+        assert_eq!(flood_wait_secs("rpc error 400: MSG_ID_INVALID"), None);
+        assert_eq!(flood_wait_secs("FLOOD_WAIT"), None);
+    }
+}
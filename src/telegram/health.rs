@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+/// Connection health of the Telegram client, shared between the scraper and
+/// `/healthz`, `/health` and `/ready`.
+#[derive(Clone, Default)]
+pub struct TelegramHealth {
+    inner: Arc<RwLock<HealthSnapshot>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HealthSnapshot {
+    pub connected: bool,
+    /// The session is signed in (false once Telegram rejects it)
+    pub authorized: bool,
+    pub last_poll_ok: Option<DateTime<Utc>>,
+    /// Last successful poll per channel
+    pub channel_polls: BTreeMap<String, DateTime<Utc>>,
+    /// Passes over the channel list with at least one successful poll (or an
+    /// empty list, reported as `no_channels`)
+    pub poll_cycles: u64,
+    pub last_error: Option<String>,
+    pub reconnects: u32,
+    /// The channel list is empty, so nothing is being polled
+    pub no_channels: bool,
+}
+
+impl TelegramHealth {
+    pub fn snapshot(&self) -> HealthSnapshot {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.update(|s| s.connected = connected);
+    }
+
+    pub fn set_authorized(&self, authorized: bool) {
+        self.update(|s| s.authorized = authorized);
+    }
+
+    pub fn poll_ok(&self, channel: &str) {
+        let now = Utc::now();
+        self.update(|s| {
+            s.connected = true;
+            s.last_poll_ok = Some(now);
+            s.channel_polls.insert(channel.to_string(), now);
+        });
+    }
+
+    pub fn cycle_done(&self) {
+        self.update(|s| s.poll_cycles += 1);
+    }
+
+    pub fn poll_error(&self, error: String) {
+        self.update(|s| s.last_error = Some(error));
+    }
+
+    pub fn set_no_channels(&self, no_channels: bool) {
+        self.update(|s| s.no_channels = no_channels);
+    }
+
+    pub fn reconnected(&self) {
+        self.update(|s| {
+            s.connected = true;
+            s.authorized = true;
+            s.reconnects += 1;
+        });
+    }
+
+    fn update(&self, f: impl FnOnce(&mut HealthSnapshot)) {
+        f(&mut self.inner.write().unwrap_or_else(|e| e.into_inner()));
+    }
+}
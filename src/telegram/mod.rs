@@ -1,5 +1,11 @@
+pub mod channel_ref;
 pub mod client;
+pub mod cursor;
+pub mod flood;
+pub mod health;
+pub mod phone;
 pub mod types;
 
 pub use client::TelegramScraper;
+pub use health::TelegramHealth;
 pub use types::RawComment;
@@ -0,0 +1,40 @@
+use phonenumber::{country, Mode};
+
+/// Normalizes a phone number to E.164.
+///
+/// Stray characters (spaces, dashes, brackets, labels) are dropped first.
+/// Numbers starting with `+` are parsed as international. Bare numbers are
+/// tried as international too, since that is how Telegram reports account
+/// phones (`79001234567`), then in `default_country` if one is given. When
+/// neither gives a valid number, `None` is returned and only the raw form is kept.
+pub fn normalize(raw: &str, default_country: Option<country::Id>) -> Option<String> {
+    let digits: String = raw.chars().filter(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        return None;
+    }
+
+    let international = format!("+{}", digits);
+    if raw.trim_start().starts_with('+') {
+        return parse_valid(None, &international);
+    }
+    parse_valid(None, &international).or_else(|| parse_valid(Some(default_country?), &digits))
+}
+
+fn parse_valid(region: Option<country::Id>, number: &str) -> Option<String> {
+    let number = phonenumber::parse(region, number).ok()?;
+    phonenumber::is_valid(&number).then(|| number.format().mode(Mode::E164).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_telegram_and_local_forms() {
+        assert_eq!(normalize("79161234567", None).as_deref(), Some("+79161234567"));
+        assert_eq!(normalize(" +7 (916) 123-45-67 ", None).as_deref(), Some("+79161234567"));
+        assert_eq!(normalize("8 916 123 45 67", Some(country::Id::RU)).as_deref(), Some("+79161234567"));
+        assert_eq!(normalize("8 916 123 45 67", None), None);
+        assert_eq!(normalize("n/a", Some(country::Id::RU)), None);
+    }
+}
@@ -1,14 +1,63 @@
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RawComment {
     pub channel: String,
     pub post_id: i32,
     pub comment_id: i32,
+    /// Telegram user ID of the author (0 if anonymous / sent as a channel)
+    pub author_id: i64,
     pub author: String,
     pub username: Option<String>,
     pub phone: Option<String>,
+    /// `phone` in E.164 form, when it could be normalized
+    #[serde(default)]
+    pub phone_e164: Option<String>,
     pub text: String,
+    /// Text of the channel post this comment is under (empty for media-only posts)
+    #[serde(default)]
+    pub post_text: String,
     pub date: DateTime<Utc>,
+    /// (emoji, count) per reaction on the comment when it was fetched.
+    /// Custom emoji are `custom:<document id>`, paid stars are `⭐`.
+    #[serde(default)]
+    pub reactions: Vec<(String, i32)>,
+    /// New comments on the parent post since the previous poll
+    pub comment_velocity: u32,
+    /// Comment this one replies to (None for a direct reply to the post)
+    #[serde(default)]
+    pub reply_to_comment_id: Option<i32>,
+    /// The author's profile "about" text, when `fetch_author_bios` is on
+    #[serde(default)]
+    pub author_bio: Option<String>,
+    /// Author has Telegram Premium (false when the user is unknown)
+    #[serde(default)]
+    pub is_premium: bool,
+    /// Author is a verified account (false when the user is unknown)
+    #[serde(default)]
+    pub is_verified: bool,
+    /// Forum topic the comment was posted in (then `post_id` is the topic's ID)
+    #[serde(default)]
+    pub topic_id: Option<i32>,
+    /// Re-emitted because the comment was edited after it was first seen
+    #[serde(default)]
+    pub edited: bool,
+    /// View and forward counts; Telegram usually only reports them for channel
+    /// posts, so discussion replies mostly have None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub views: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forwards: Option<i32>,
+    /// Comments by the same author combined into this one for classification
+    /// (this one last, `text` holding all of theirs); empty for a single comment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregated_comment_ids: Vec<i32>,
+}
+
+impl RawComment {
+    /// Sum of all reaction counts.
+    pub fn reaction_total(&self) -> u32 {
+        self.reactions.iter().map(|(_, n)| (*n).max(0) as u32).sum()
+    }
 }
@@ -0,0 +1,528 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::analysis::usage::UsageSnapshot;
+use crate::analysis::AnalyzedComment;
+use crate::diagnostics::ErrorEntry;
+use crate::storage::channels::ChannelEntry;
+use crate::storage::kafka_metrics::KafkaSnapshot;
+use crate::storage::{self, CrmLead};
+use crate::telegram::RawComment;
+use crate::telegram::health::HealthSnapshot;
+use super::state::{AppState, SelfTestReport};
+
+/// GET /metrics/usage — analysis token totals and estimated cost since startup.
+pub async fn usage(State(state): State<AppState>) -> Json<UsageSnapshot> {
+    Json(state.analyzer.usage())
+}
+
+/// GET /metrics/kafka — Kafka sink delivery counters since startup (404 without a sink).
+pub async fn kafka(State(state): State<AppState>) -> Result<Json<KafkaSnapshot>, StatusCode> {
+    let metrics = state.kafka.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(metrics.snapshot()))
+}
+
+#[derive(Serialize)]
+pub struct AuthorResponse {
+    id: String,
+    total_comments: usize,
+    leads: usize,
+    comments: Vec<AnalyzedComment>,
+}
+
+/// GET /api/authors/{id} — all analyzed comments by an author (ID or username).
+pub async fn author(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<AuthorResponse>, StatusCode> {
+    let authors = state.authors.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let comments = authors.get(&id).await.ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(AuthorResponse {
+        id,
+        total_comments: comments.len(),
+        leads: comments.iter().filter(|c| c.is_lead).count(),
+        comments,
+    }))
+}
+
+const SELFTEST_TEXT: &str =
+    "Я владелец агентства недвижимости, ищем способ находить клиентов в Telegram-каналах";
+
+/// GET /api/selftest — runs a known sample comment through the analyzer and reports
+/// latency and result. Runs at most once per `selftest_interval`; cached otherwise.
+pub async fn selftest(State(state): State<AppState>) -> (StatusCode, Json<SelfTestReport>) {
+    // Holding the lock for the whole run also keeps concurrent callers from piling up
+    let mut last = state.selftest.lock().await;
+    if let Some((at, report)) = last.as_ref() {
+        if at.elapsed() < state.selftest_interval {
+            return (selftest_status(report), Json(report.clone()));
+        }
+    }
+
+    let sample = RawComment {
+        channel: "selftest".to_string(),
+        post_id: 0,
+        comment_id: 0,
+        author_id: 0,
+        author: "selftest".to_string(),
+        username: None,
+        phone: None,
+        phone_e164: None,
+        text: SELFTEST_TEXT.to_string(),
+        post_text: String::new(),
+        date: Utc::now(),
+        reactions: Vec::new(),
+        comment_velocity: 0,
+        reply_to_comment_id: None,
+        author_bio: None,
+        is_premium: false,
+        is_verified: false,
+        topic_id: None,
+        edited: false,
+        views: None,
+        forwards: None,
+        aggregated_comment_ids: Vec::new(),
+    };
+
+    let started = Instant::now();
+    let result = state.analyzer.analyze(&sample).await;
+    let latency_ms = started.elapsed().as_millis();
+
+    let report = match result {
+        Ok(c) => SelfTestReport {
+            ok: true,
+            latency_ms,
+            checked_at: Utc::now(),
+            intent: Some(c.intent.clone()),
+            is_lead: Some(c.is_lead),
+            lead_score: Some(c.lead_score),
+            error: None,
+        },
+        Err(e) => SelfTestReport {
+            ok: false,
+            latency_ms,
+            checked_at: Utc::now(),
+            intent: None,
+            is_lead: None,
+            lead_score: None,
+            error: Some(format!("{:#}", e)),
+        },
+    };
+
+    *last = Some((Instant::now(), report.clone()));
+    (selftest_status(&report), Json(report))
+}
+
+fn selftest_status(report: &SelfTestReport) -> StatusCode {
+    if report.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewLeadsQuery {
+    since: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct NewLeadsResponse {
+    since: DateTime<Utc>,
+    total: usize,
+    leads: Vec<NewLead>,
+}
+
+#[derive(Serialize)]
+pub struct NewLead {
+    first_seen: DateTime<Utc>,
+    #[serde(flatten)]
+    comment: AnalyzedComment,
+}
+
+/// GET /api/leads/new?since=<iso8601> — unique leads first discovered after `since`,
+/// one entry per lead (its best-scoring comment), sorted by score.
+pub async fn new_leads(
+    State(state): State<AppState>,
+    Query(query): Query<NewLeadsQuery>,
+) -> Json<NewLeadsResponse> {
+    let first_seen = state.lead_first_seen.read().await;
+    let leads = state.leads.read().await;
+
+    // `leads` is sorted by score, so the first comment per key is its best one
+    let mut best: HashMap<String, NewLead> = HashMap::new();
+    for c in leads.iter() {
+        let key = c.lead_key();
+        let Some(&seen_at) = first_seen.get(&key) else {
+            continue;
+        };
+        if seen_at > query.since && !best.contains_key(&key) {
+            best.insert(
+                key,
+                NewLead {
+                    first_seen: seen_at,
+                    comment: c.clone(),
+                },
+            );
+        }
+    }
+
+    let mut leads: Vec<NewLead> = best.into_values().collect();
+    leads.sort_by(|a, b| {
+        b.comment
+            .lead_score
+            .partial_cmp(&a.comment.lead_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Json(NewLeadsResponse {
+        since: query.since,
+        total: leads.len(),
+        leads,
+    })
+}
+
+#[derive(Serialize)]
+pub struct HealthResponse {
+    /// "ok", "degraded" (connected but nothing to poll) or "down"
+    status: &'static str,
+    #[serde(flatten)]
+    telegram: HealthSnapshot,
+}
+
+#[derive(Serialize)]
+pub struct CrmLeadsResponse {
+    generated_at: DateTime<Utc>,
+    total: usize,
+    leads: Vec<CrmLead>,
+}
+
+/// GET /api/leads/crm — one normalized record per unique lead (its best-scoring
+/// comment), sorted by score. See `CrmLead` for the field types.
+pub async fn crm_leads(State(state): State<AppState>) -> Json<CrmLeadsResponse> {
+    let leads = state.leads.read().await;
+
+    // `leads` is sorted by score, so the first comment per key is its best one
+    let mut seen = HashSet::new();
+    let leads: Vec<CrmLead> = leads
+        .iter()
+        .filter(|c| seen.insert(c.lead_key()))
+        .map(CrmLead::from)
+        .collect();
+
+    Json(CrmLeadsResponse {
+        generated_at: Utc::now(),
+        total: leads.len(),
+        leads,
+    })
+}
+
+/// GET /api/leads.csv — current leads as a CSV download, deduplicated and
+/// ranked like leads.json.
+pub async fn leads_csv(
+    State(state): State<AppState>,
+) -> Result<([(header::HeaderName, &'static str); 2], Vec<u8>), (StatusCode, String)> {
+    let leads = state.leads.read().await;
+    let csv = storage::leads_csv(&leads)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, r#"attachment; filename="leads.csv""#),
+        ],
+        csv,
+    ))
+}
+
+/// GET /api/leads.vcf — one vCard per unique lead (its best-scoring comment)
+/// that has a phone or username, for import into an address book.
+pub async fn leads_vcf(
+    State(state): State<AppState>,
+) -> ([(header::HeaderName, &'static str); 2], String) {
+    let leads = state.leads.read().await;
+    // `leads` is sorted by score, so the first comment per key is its best one
+    let mut seen = HashSet::new();
+    let vcf = storage::vcard::leads_vcf(leads.iter().filter(|c| seen.insert(c.lead_key())));
+    (
+        [
+            (header::CONTENT_TYPE, "text/vcard; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, r#"attachment; filename="leads.vcf""#),
+        ],
+        vcf,
+    )
+}
+
+#[derive(Serialize)]
+pub struct ContactedResponse {
+    channel: String,
+    comment_id: i32,
+    contacted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contacted_at: Option<DateTime<Utc>>,
+}
+
+/// POST /api/leads/{channel}/{comment_id}/contacted — marks a lead as contacted.
+/// Repeating it keeps the original `contacted_at`; 404 for an unknown lead.
+pub async fn mark_contacted(
+    State(state): State<AppState>,
+    Path((channel, comment_id)): Path<(String, i32)>,
+) -> Result<Json<ContactedResponse>, StatusCode> {
+    set_contacted(&state, &channel, comment_id, true).await
+}
+
+/// DELETE /api/leads/{channel}/{comment_id}/contacted — clears the mark.
+pub async fn unmark_contacted(
+    State(state): State<AppState>,
+    Path((channel, comment_id)): Path<(String, i32)>,
+) -> Result<Json<ContactedResponse>, StatusCode> {
+    set_contacted(&state, &channel, comment_id, false).await
+}
+
+async fn set_contacted(
+    state: &AppState,
+    channel: &str,
+    comment_id: i32,
+    contacted: bool,
+) -> Result<Json<ContactedResponse>, StatusCode> {
+    let lead = state
+        .set_contacted(channel, comment_id, contacted)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ContactedResponse {
+        channel: lead.channel,
+        comment_id: lead.comment_id,
+        contacted: lead.contacted,
+        contacted_at: lead.contacted_at,
+    }))
+}
+
+/// GET /healthz — Telegram connection health; 503 while disconnected.
+pub async fn healthz(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let snapshot = state.telegram_health.snapshot();
+    let (code, status) = if !snapshot.connected {
+        (StatusCode::SERVICE_UNAVAILABLE, "down")
+    } else if snapshot.no_channels {
+        (StatusCode::OK, "degraded")
+    } else {
+        (StatusCode::OK, "ok")
+    };
+    (
+        code,
+        Json(HealthResponse {
+            status,
+            telegram: snapshot,
+        }),
+    )
+}
+
+#[derive(Serialize)]
+pub struct LivenessResponse {
+    uptime_secs: u64,
+    telegram_authorized: bool,
+    /// Last successful poll per channel
+    channels: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// GET /health — liveness: uptime, Telegram authorization and last poll per channel.
+/// Always 200 while the process is serving.
+pub async fn health(State(state): State<AppState>) -> Json<LivenessResponse> {
+    let snapshot = state.telegram_health.snapshot();
+    Json(LivenessResponse {
+        uptime_secs: state.started_at.elapsed().as_secs(),
+        telegram_authorized: snapshot.authorized,
+        channels: snapshot.channel_polls,
+    })
+}
+
+#[derive(Serialize)]
+pub struct ReadinessResponse {
+    ready: bool,
+    poll_cycles: u64,
+}
+
+/// GET /ready — readiness: 503 until a poll cycle has polled a channel successfully
+/// (or found the channel list empty).
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let poll_cycles = state.telegram_health.snapshot().poll_cycles;
+    let ready = poll_cycles > 0;
+    let code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(ReadinessResponse { ready, poll_cycles }))
+}
+
+#[derive(Serialize)]
+pub struct ChannelsResponse {
+    generated_at: DateTime<Utc>,
+    /// Sum of `leads_found` over all channels
+    total_leads: usize,
+    /// Distinct people among the leads, across channels
+    unique_leads: usize,
+    channels: Vec<ChannelEntry>,
+}
+
+/// GET /api/channels — per-channel counts and lead rates, as in channels.json.
+pub async fn channels(State(state): State<AppState>) -> Json<ChannelsResponse> {
+    let channels = state.channel_stats.read().await.entries();
+    Json(ChannelsResponse {
+        generated_at: Utc::now(),
+        total_leads: channels.iter().map(|c| c.leads_found).sum(),
+        unique_leads: state.lead_people.read().await.len(),
+        channels,
+    })
+}
+
+#[derive(Serialize)]
+pub struct ErrorsResponse {
+    total: u64,
+    errors: Vec<ErrorEntry>,
+}
+
+/// GET /api/errors — recent errors from all components, newest first.
+pub async fn errors(State(state): State<AppState>) -> Json<ErrorsResponse> {
+    Json(ErrorsResponse {
+        total: state.error_log.total(),
+        errors: state.error_log.recent(),
+    })
+}
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+const MAX_SEARCH_LIMIT: usize = 200;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SearchResponse {
+    query: String,
+    total: usize,
+    comments: Vec<AnalyzedComment>,
+}
+
+/// GET /api/search?q=&limit= — recent comments whose text or author contains every
+/// word of `q` (case-insensitive), newest first.
+pub async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<SearchResponse>, (StatusCode, String)> {
+    let terms: Vec<String> = query.q.split_whitespace().map(fold_case).collect();
+    if terms.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "q must not be empty".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    let recent = state.recent.read().await;
+    let comments: Vec<AnalyzedComment> = recent
+        .iter()
+        .rev()
+        .filter(|c| {
+            let haystack = fold_case(&format!(
+                "{} {} {}",
+                c.text,
+                c.author,
+                c.username.as_deref().unwrap_or_default()
+            ));
+            terms.iter().all(|t| haystack.contains(t.as_str()))
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+
+    Ok(Json(SearchResponse {
+        query: query.q,
+        total: comments.len(),
+        comments,
+    }))
+}
+
+/// Lowercases (Unicode-aware, so Cyrillic works) and folds ё into е, which
+/// people use interchangeably.
+fn fold_case(s: &str) -> String {
+    s.to_lowercase().replace('ё', "е")
+}
+
+#[derive(Deserialize)]
+pub struct AnalyzeRequest {
+    text: String,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+}
+
+/// POST /api/analyze — classifies arbitrary text with the pipeline's analyzer.
+/// Limited to `analyze_rate_per_min` calls and the analyzer's concurrency limit.
+pub async fn analyze(
+    State(state): State<AppState>,
+    Json(request): Json<AnalyzeRequest>,
+) -> Result<Json<AnalyzedComment>, (StatusCode, String)> {
+    if state.analyze_rate_per_min == 0 {
+        return Err((StatusCode::NOT_FOUND, "Analysis endpoint is disabled".to_string()));
+    }
+    if request.text.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "text must not be empty".to_string()));
+    }
+
+    {
+        let mut calls = state.analyze_calls.lock().await;
+        while calls
+            .front()
+            .is_some_and(|at| at.elapsed() >= Duration::from_secs(60))
+        {
+            calls.pop_front();
+        }
+        if calls.len() >= state.analyze_rate_per_min {
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Limit is {} requests per minute", state.analyze_rate_per_min),
+            ));
+        }
+        calls.push_back(Instant::now());
+    }
+
+    let comment = RawComment {
+        channel: request.channel.unwrap_or_else(|| "api".to_string()),
+        post_id: 0,
+        comment_id: 0,
+        author_id: 0,
+        author: request.author.unwrap_or_else(|| "api".to_string()),
+        username: None,
+        phone: None,
+        phone_e164: None,
+        text: request.text,
+        post_text: String::new(),
+        date: Utc::now(),
+        reactions: Vec::new(),
+        comment_velocity: 0,
+        reply_to_comment_id: None,
+        author_bio: None,
+        is_premium: false,
+        is_verified: false,
+        topic_id: None,
+        edited: false,
+        views: None,
+        forwards: None,
+        aggregated_comment_ids: Vec::new(),
+    };
+
+    state
+        .analyzer
+        .analyze_limited(&comment)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{:#}", e)))
+}
@@ -0,0 +1,131 @@
+use axum::extract::{Request, State};
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use tracing::{error, warn};
+
+use crate::config::AuthConfig;
+use super::state::AppState;
+
+const CHALLENGE: &str = r#"Basic realm="atento", charset="UTF-8""#;
+
+/// HTTP basic auth credentials for the web server (`web.auth`).
+pub struct BasicAuth {
+    username: String,
+    password: Password,
+    /// SHA-256 of the last accepted Authorization header, so bcrypt runs once per
+    /// login rather than on every request
+    accepted: Mutex<Option<[u8; 32]>>,
+}
+
+enum Password {
+    Plain([u8; 32]),
+    Bcrypt(String),
+}
+
+impl BasicAuth {
+    pub fn new(config: &AuthConfig) -> Self {
+        let password = match (&config.password_hash, &config.password) {
+            (Some(hash), _) => Password::Bcrypt(hash.clone()),
+            (None, Some(plain)) => Password::Plain(digest(plain.as_bytes())),
+            // Rejected by config validation
+            (None, None) => Password::Bcrypt(String::new()),
+        };
+        Self {
+            username: config.username.clone(),
+            password,
+            accepted: Mutex::new(None),
+        }
+    }
+
+    fn check(&self, header: &str) -> bool {
+        let header_digest = digest(header.as_bytes());
+        if *self.accepted.lock().unwrap() == Some(header_digest) {
+            return true;
+        }
+
+        let Some((username, password)) = parse_basic(header) else {
+            return false;
+        };
+        // Digests are compared so the time taken doesn't depend on how much matches
+        let user_ok = digest(username.as_bytes()) == digest(self.username.as_bytes());
+        let password_ok = match &self.password {
+            Password::Plain(expected) => digest(password.as_bytes()) == *expected,
+            Password::Bcrypt(hash) => bcrypt::verify(&password, hash).unwrap_or_else(|e| {
+                error!("Invalid web.auth.password_hash: {}", e);
+                false
+            }),
+        };
+
+        let ok = user_ok && password_ok;
+        if ok {
+            *self.accepted.lock().unwrap() = Some(header_digest);
+        }
+        ok
+    }
+}
+
+/// Rejects requests without valid credentials when `web.auth` is configured.
+pub async fn require_auth(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(auth) = &state.auth else {
+        return next.run(request).await;
+    };
+
+    let header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    if header.is_some_and(|h| auth.check(h)) {
+        return next.run(request).await;
+    }
+    if header.is_some() {
+        warn!("Rejected web login for {}", request.uri().path());
+    }
+    (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, CHALLENGE)]).into_response()
+}
+
+/// `Basic <base64 user:password>` → (user, password).
+fn parse_basic(header: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+fn digest(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain_auth() -> BasicAuth {
+        BasicAuth::new(&AuthConfig {
+            username: "sales".to_string(),
+            password: Some("s3cret:pass".to_string()),
+            password_hash: None,
+        })
+    }
+
+    #[test]
+    fn accepts_only_matching_credentials() {
+        let auth = plain_auth();
+        // base64("sales:s3cret:pass") — passwords may contain colons
+        assert!(auth.check("Basic c2FsZXM6czNjcmV0OnBhc3M="));
+        // base64("sales:wrong")
+        assert!(!auth.check("Basic c2FsZXM6d3Jvbmc="));
+        assert!(!auth.check("Bearer c2FsZXM6czNjcmV0OnBhc3M="));
+        assert!(!auth.check("Basic not-base64!"));
+    }
+}
@@ -1,9 +1,12 @@
+pub mod api;
+pub mod auth;
 pub mod routes;
 pub mod sse;
 pub mod state;
 
+use axum::middleware;
 use axum::Router;
-use axum::routing::get;
+use axum::routing::{get, post};
 use tower_http::services::ServeDir;
 
 use state::AppState;
@@ -11,7 +14,28 @@ use state::AppState;
 pub fn create_router(state: AppState) -> Router {
     Router::new()
         .route("/", get(routes::dashboard))
+        .route("/lead/{channel}/{comment_id}", get(routes::lead))
         .route("/sse", get(sse::sse_handler))
+        .route("/healthz", get(api::healthz))
+        .route("/health", get(api::health))
+        .route("/ready", get(api::ready))
+        .route("/metrics/usage", get(api::usage))
+        .route("/metrics/kafka", get(api::kafka))
+        .route("/api/authors/{id}", get(api::author))
+        .route("/api/selftest", get(api::selftest))
+        .route("/api/leads/new", get(api::new_leads))
+        .route("/api/leads/crm", get(api::crm_leads))
+        .route("/api/leads.csv", get(api::leads_csv))
+        .route("/api/leads.vcf", get(api::leads_vcf))
+        .route(
+            "/api/leads/{channel}/{comment_id}/contacted",
+            post(api::mark_contacted).delete(api::unmark_contacted),
+        )
+        .route("/api/errors", get(api::errors))
+        .route("/api/channels", get(api::channels))
+        .route("/api/search", get(api::search))
+        .route("/api/analyze", post(api::analyze))
         .nest_service("/static", ServeDir::new("templates/static"))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::require_auth))
         .with_state(state)
 }
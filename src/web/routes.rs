@@ -1,63 +1,282 @@
 use askama::Template;
-use axum::extract::State;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
 use axum::response::Html;
+use serde::Deserialize;
 
-use crate::analysis::Intent;
-use super::state::AppState;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::analysis::{AnalyzedComment, Intent};
+use super::state::{AppState, Stats};
+
+const DEFAULT_PER_PAGE: usize = 100;
+const MAX_PER_PAGE: usize = 500;
 
 #[derive(Template)]
 #[template(path = "dashboard.html")]
 struct DashboardTemplate {
+    title: String,
+    logo_url: Option<String>,
     comments: Vec<CommentView>,
+    /// Rendered `StatsPanelTemplate`; also pushed over SSE as `stats` events
+    stats_panel: String,
+    page: usize,
+    pages: usize,
+    per_page: usize,
+    /// Comments on the dashboard across all pages
+    shown_total: usize,
+    prev_page: Option<usize>,
+    next_page: Option<usize>,
+    /// Active filters, as their query values
+    intent_filter: Option<String>,
+    channel_filter: Option<String>,
+    /// Shown when a filter value was ignored
+    filter_note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct DashboardQuery {
+    #[serde(default)]
+    page: Option<usize>,
+    #[serde(default)]
+    per_page: Option<usize>,
+    /// Intent key, e.g. `realtor_agency`
+    #[serde(default)]
+    intent: Option<String>,
+    /// Channel username, with or without `@`
+    #[serde(default)]
+    channel: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "stats_panel.html")]
+struct StatsPanelTemplate {
     total: usize,
     leads: usize,
+    unique_leads: usize,
     lead_rate: String,
     stats: Vec<(String, usize)>,
+    errors: u64,
+}
+
+#[derive(Template)]
+#[template(path = "lead.html")]
+struct LeadTemplate {
+    title: String,
+    logo_url: Option<String>,
+    /// None renders the not-found message
+    lead: Option<LeadView>,
+}
+
+/// Everything known about one comment, untruncated.
+struct LeadView {
+    is_lead: bool,
+    lead_score: String,
+    need_summary: String,
+    contacted: bool,
+    contacted_at: Option<String>,
+    channel: String,
+    author: String,
+    is_premium: bool,
+    is_verified: bool,
+    username: String,
+    phone: String,
+    /// `phone` is the raw capture, not a valid E.164 number
+    phone_unverified: bool,
+    author_bio: Option<String>,
+    text: String,
+    url: Option<String>,
+    post_text: String,
+    post_url: Option<String>,
+    /// The comment this one answers: its ID, author and text (if still in memory)
+    reply_to: Option<(i32, Option<(String, String)>)>,
+    reactions: String,
+    intent: String,
+    intent_css: String,
+    confidence: String,
+    sentiment: String,
+    sentiment_css: &'static str,
+    date: String,
+    analyzed_at: String,
+    velocity: String,
 }
 
 struct CommentView {
     is_lead: bool,
+    contacted: bool,
+    comment_id: i32,
     lead_score: String,
     need_summary: String,
     channel: String,
     author: String,
+    is_premium: bool,
+    is_verified: bool,
     username: String,
     phone: String,
+    phone_unverified: bool,
     text: String,
+    truncated: bool,
+    url: Option<String>,
     intent: String,
     intent_css: String,
     confidence: String,
+    sentiment: String,
+    sentiment_css: &'static str,
     date: String,
+    velocity: String,
+    reactions: String,
 }
 
-pub async fn dashboard(State(state): State<AppState>) -> Html<String> {
+/// GET /?page=&per_page=&intent=&channel= — leads and recent comments, filtered
+/// and one page at a time. The stats header always covers the full dataset.
+pub async fn dashboard(
+    State(state): State<AppState>,
+    Query(query): Query<DashboardQuery>,
+) -> Html<String> {
     let recent = state.recent.read().await;
     let leads = state.leads.read().await;
     let stats = state.stats.read().await;
 
-    // Show all leads first (from dedicated leads buffer), then recent non-lead comments
-    let lead_views: Vec<_> = leads.iter().collect();
-    let recent_non_leads: Vec<_> = recent.iter().filter(|c| !c.is_lead).collect();
-    let combined: Vec<_> = lead_views.into_iter().chain(recent_non_leads).collect();
+    let mut filter_note = None;
+    let intent = match query.intent.as_deref().filter(|i| !i.is_empty()) {
+        Some(key) => {
+            let intent = Intent::known(key);
+            if intent.is_none() {
+                filter_note = Some(format!("Unknown intent \"{}\", showing all intents", key));
+            }
+            intent
+        }
+        None => None,
+    };
+    let channel = query
+        .channel
+        .as_deref()
+        .map(|c| c.trim().trim_start_matches('@'))
+        .filter(|c| !c.is_empty());
+
+    let mut combined = combine_for_dashboard(&leads, recent.iter());
+    combined.retain(|c| {
+        intent.as_ref().is_none_or(|i| c.intent == *i)
+            && channel.is_none_or(|ch| c.channel.trim_start_matches('@').eq_ignore_ascii_case(ch))
+    });
+
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+    let (page, pages, range) = paginate(combined.len(), query.page.unwrap_or(1), per_page);
 
-    let comments: Vec<CommentView> = combined
+    let comments: Vec<CommentView> = combined[range]
         .iter()
-        .map(|c| CommentView {
-            is_lead: c.is_lead,
-            lead_score: format!("{:.0}%", c.lead_score * 100.0),
-            need_summary: c.need_summary.clone(),
-            channel: format!("@{}", c.channel),
-            author: c.author.clone(),
-            username: c.username.as_deref().map(|u| format!("@{}", u)).unwrap_or_default(),
-            phone: c.phone.clone().unwrap_or_default(),
-            text: c.text.clone(),
-            intent: c.intent.to_string(),
-            intent_css: c.intent.css_class().to_string(),
-            confidence: format!("{:.0}%", c.confidence * 100.0),
-            date: c.date.format("%H:%M:%S").to_string(),
+        .map(|c| {
+            let (text, truncated) = c.text_preview(state.preview_chars);
+            CommentView {
+                is_lead: c.is_lead,
+                contacted: c.contacted,
+                comment_id: c.comment_id,
+                lead_score: format!("{:.0}%", c.lead_score * 100.0),
+                need_summary: c.need_summary.clone(),
+                channel: c.channel.clone(),
+                author: c.author.clone(),
+                is_premium: c.is_premium,
+                is_verified: c.is_verified,
+                username: c.username.as_deref().map(|u| format!("@{}", u)).unwrap_or_default(),
+                phone: c.phone_display().unwrap_or_default().to_string(),
+                phone_unverified: c.phone_unverified(),
+                text,
+                truncated,
+                url: c.comment_url(),
+                intent: c.intent.to_string(),
+                intent_css: c.intent.css_class().to_string(),
+                confidence: format!("{:.0}%", c.confidence * 100.0),
+                sentiment: format_sentiment(c.sentiment),
+                sentiment_css: sentiment_css(c.sentiment),
+                date: c.date.format("%H:%M:%S").to_string(),
+                velocity: format_velocity(c.comment_velocity),
+                reactions: c.reactions_display(),
+            }
         })
         .collect();
 
+    let template = DashboardTemplate {
+        title: state.title.clone(),
+        logo_url: state.logo_url.clone(),
+        comments,
+        stats_panel: render_stats_panel(&stats, state.error_log.total()),
+        page,
+        pages,
+        per_page,
+        shown_total: combined.len(),
+        prev_page: (page > 1).then(|| page - 1),
+        next_page: (page < pages).then(|| page + 1),
+        intent_filter: intent.map(|i| i.key().to_string()),
+        channel_filter: channel.map(str::to_string),
+        filter_note,
+    };
+
+    Html(template.render().unwrap_or_else(|e| format!("Template error: {}", e)))
+}
+
+/// GET /lead/{channel}/{comment_id} — one comment (usually a lead) with its full
+/// text, context and analysis. Looked up in the leads, then the recent buffer.
+pub async fn lead(
+    State(state): State<AppState>,
+    Path((channel, comment_id)): Path<(String, i32)>,
+) -> (StatusCode, Html<String>) {
+    let recent = state.recent.read().await;
+    let leads = state.leads.read().await;
+
+    let channel = channel.trim_start_matches('@');
+    let in_channel = |c: &&AnalyzedComment| c.channel.trim_start_matches('@').eq_ignore_ascii_case(channel);
+    let find = |id: i32| {
+        leads
+            .iter()
+            .chain(recent.iter())
+            .filter(in_channel)
+            .find(|c| c.comment_id == id)
+    };
+
+    let lead = find(comment_id).map(|c| LeadView {
+        is_lead: c.is_lead,
+        lead_score: format!("{:.0}%", c.lead_score * 100.0),
+        need_summary: c.need_summary.clone(),
+        contacted: c.contacted,
+        contacted_at: c.contacted_at.map(|at| at.format("%Y-%m-%d %H:%M").to_string()),
+        channel: c.channel.clone(),
+        author: c.author.clone(),
+        is_premium: c.is_premium,
+        is_verified: c.is_verified,
+        username: c.username.as_deref().map(|u| format!("@{}", u)).unwrap_or_default(),
+        phone: c.phone_display().unwrap_or_default().to_string(),
+        phone_unverified: c.phone_unverified(),
+        author_bio: c.author_bio.clone(),
+        text: c.text.clone(),
+        url: c.comment_url(),
+        post_text: c.post_text.clone(),
+        post_url: c.post_url(),
+        reply_to: c.reply_to_comment_id.map(|id| {
+            (id, find(id).map(|parent| (parent.author.clone(), parent.text.clone())))
+        }),
+        reactions: c.reactions_display(),
+        intent: c.intent.to_string(),
+        intent_css: c.intent.css_class().to_string(),
+        confidence: format!("{:.0}%", c.confidence * 100.0),
+        sentiment: format_sentiment(c.sentiment),
+        sentiment_css: sentiment_css(c.sentiment),
+        date: c.date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        analyzed_at: c.analyzed_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        velocity: format_velocity(c.comment_velocity),
+    });
+
+    let status = if lead.is_some() { StatusCode::OK } else { StatusCode::NOT_FOUND };
+    let template = LeadTemplate {
+        title: state.title.clone(),
+        logo_url: state.logo_url.clone(),
+        lead,
+    };
+    (status, Html(template.render().unwrap_or_else(|e| format!("Template error: {}", e))))
+}
+
+/// The dashboard header counters as an HTML fragment.
+pub fn render_stats_panel(stats: &Stats, errors: u64) -> String {
     let lead_rate = if stats.total > 0 {
         format!("{:.0}%", (stats.leads as f64 / stats.total as f64) * 100.0)
     } else {
@@ -77,13 +296,161 @@ pub async fn dashboard(State(state): State<AppState>) -> Html<String> {
         .collect();
     intent_stats.sort_by(|a, b| b.1.cmp(&a.1));
 
-    let template = DashboardTemplate {
-        comments,
+    let template = StatsPanelTemplate {
         total: stats.total,
         leads: stats.leads,
+        unique_leads: stats.unique_leads,
         lead_rate,
         stats: intent_stats,
+        errors,
     };
+    template.render().unwrap_or_else(|e| format!("Template error: {}", e))
+}
 
-    Html(template.render().unwrap_or_else(|e| format!("Template error: {}", e)))
+/// Leads first (from the dedicated leads buffer), then recent non-lead comments,
+/// with each comment shown once (by channel and comment ID, as `same_comment`).
+/// A comment in both buffers keeps its leads-buffer version; duplicates within a
+/// buffer keep the most recent analysis.
+fn combine_for_dashboard<'a>(
+    leads: &'a [AnalyzedComment],
+    recent: impl IntoIterator<Item = &'a AnalyzedComment>,
+) -> Vec<&'a AnalyzedComment> {
+    let mut combined: Vec<&AnalyzedComment> = Vec::new();
+    let mut index: HashMap<(&str, i32), usize> = HashMap::new();
+
+    for c in leads {
+        let key = (c.channel.as_str(), c.comment_id);
+        match index.get(&key) {
+            Some(&i) if combined[i].analyzed_at < c.analyzed_at => combined[i] = c,
+            Some(_) => {}
+            None => {
+                index.insert(key, combined.len());
+                combined.push(c);
+            }
+        }
+    }
+    let from_leads = combined.len();
+
+    for c in recent.into_iter().filter(|c| !c.is_lead) {
+        let key = (c.channel.as_str(), c.comment_id);
+        match index.get(&key) {
+            Some(&i) if i >= from_leads && combined[i].analyzed_at < c.analyzed_at => combined[i] = c,
+            Some(_) => {}
+            None => {
+                index.insert(key, combined.len());
+                combined.push(c);
+            }
+        }
+    }
+
+    combined
+}
+
+/// Clamps the 1-based `page` into range. Returns it, the page count (at least 1)
+/// and the slice of `len` items it covers.
+fn paginate(len: usize, page: usize, per_page: usize) -> (usize, usize, Range<usize>) {
+    let pages = len.div_ceil(per_page).max(1);
+    let page = page.clamp(1, pages);
+    let start = (page - 1) * per_page;
+    (page, pages, start..(start + per_page).min(len))
+}
+
+/// Sentiment with its sign, e.g. "-0.6" or "+0.3".
+pub fn format_sentiment(sentiment: f32) -> String {
+    format!("{:+.1}", sentiment)
+}
+
+/// CSS class for a clearly negative or positive tone; mild ones get none.
+pub fn sentiment_css(sentiment: f32) -> &'static str {
+    if sentiment <= -0.3 {
+        "negative"
+    } else if sentiment >= 0.3 {
+        "positive"
+    } else {
+        ""
+    }
+}
+
+pub fn format_velocity(velocity: u32) -> String {
+    if velocity > 0 {
+        format!("+{}", velocity)
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::types::fixtures::analyzed_comment;
+    use chrono::{Duration, Utc};
+
+    fn comment(comment_id: i32, is_lead: bool, age_secs: i64) -> AnalyzedComment {
+        let mut c = analyzed_comment(comment_id);
+        c.is_lead = is_lead;
+        c.analyzed_at = Utc::now() - Duration::seconds(age_secs);
+        c
+    }
+
+    fn ids(combined: &[&AnalyzedComment]) -> Vec<(i32, bool)> {
+        combined.iter().map(|c| (c.comment_id, c.is_lead)).collect()
+    }
+
+    #[test]
+    fn paginate_clamps_page_into_range() {
+        assert_eq!(paginate(250, 1, 100), (1, 3, 0..100));
+        assert_eq!(paginate(250, 3, 100), (3, 3, 200..250));
+        assert_eq!(paginate(250, 9, 100), (3, 3, 200..250));
+        assert_eq!(paginate(250, 0, 100), (1, 3, 0..100));
+        assert_eq!(paginate(0, 2, 100), (1, 1, 0..0));
+    }
+
+    #[test]
+    fn leads_come_first_then_non_leads() {
+        let leads = vec![comment(2, true, 0)];
+        let recent = vec![comment(1, false, 0), comment(2, true, 0), comment(3, false, 0)];
+
+        let combined = combine_for_dashboard(&leads, &recent);
+        assert_eq!(ids(&combined), vec![(2, true), (1, false), (3, false)]);
+    }
+
+    #[test]
+    fn leads_buffer_wins_over_recent() {
+        // Re-analyzed as a non-lead later, but the leads buffer still holds it
+        let leads = vec![comment(5, true, 60)];
+        let recent = vec![comment(5, false, 0)];
+
+        let combined = combine_for_dashboard(&leads, &recent);
+        assert_eq!(ids(&combined), vec![(5, true)]);
+    }
+
+    #[test]
+    fn superseded_duplicates_are_dropped() {
+        let mut newer_lead = comment(7, true, 0);
+        newer_lead.lead_score = 0.9;
+        let leads = vec![comment(7, true, 60), newer_lead];
+        let recent = vec![comment(8, false, 60), comment(8, false, 0)];
+
+        let combined = combine_for_dashboard(&leads, &recent);
+        assert_eq!(ids(&combined), vec![(7, true), (8, false)]);
+        assert_eq!(combined[0].lead_score, 0.9);
+        assert_eq!(combined[1].analyzed_at, recent[1].analyzed_at);
+    }
+
+    #[test]
+    fn comments_by_the_same_author_stay_separate() {
+        let by_anna = |id, is_lead, age_secs| {
+            let mut c = comment(id, is_lead, age_secs);
+            c.author_id = 42;
+            c
+        };
+        let mut better = by_anna(1, true, 60);
+        better.lead_score = 0.9;
+        let leads = vec![better, by_anna(2, true, 0)];
+        let recent = vec![by_anna(3, false, 0), by_anna(2, true, 0)];
+
+        let combined = combine_for_dashboard(&leads, &recent);
+        assert_eq!(ids(&combined), vec![(1, true), (2, true), (3, false)]);
+        assert_eq!(combined[0].lead_score, 0.9);
+    }
 }
@@ -1,40 +1,90 @@
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::response::sse::{Event, KeepAlive, Sse};
+use std::sync::atomic::Ordering;
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt;
 
 use crate::analysis::AnalyzedComment;
+use super::routes::{format_sentiment, format_velocity, render_stats_panel, sentiment_css};
 use super::state::AppState;
 
+/// GET /sse — live comment rows and stats. A reconnecting client's
+/// `Last-Event-Id` replays the comments it missed that are still in `recent`.
 pub async fn sse_handler(
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Sse<impl tokio_stream::Stream<Item = Result<Event, std::convert::Infallible>>> {
-    let rx = state.tx.subscribe();
-    let stream = BroadcastStream::new(rx);
+    let preview_chars = state.preview_chars;
+    let last_seen: Option<u64> = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok());
 
-    let stream = stream.filter_map(|result| {
-        match result {
-            Ok(comment) => {
-                let row_html = render_comment_row(&comment);
-                let event = Event::default()
-                    .event("comment")
-                    .data(row_html);
-                Some(Ok(event))
+    let (missed, rx) = {
+        // Subscribing under the read lock means nothing lands between replay and live
+        let recent = state.recent.read().await;
+        let rx = state.comment_tx.subscribe();
+        let missed: Vec<(u64, AnalyzedComment)> = match last_seen {
+            Some(last_seen) => {
+                let last_id = state.last_event_id.load(Ordering::SeqCst);
+                let first_id = last_id + 1 - recent.len() as u64;
+                recent
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (first_id + i as u64, c.clone()))
+                    .filter(|(id, _)| *id > last_seen)
+                    .collect()
             }
-            Err(_) => None,
+            None => Vec::new(),
+        };
+        (missed, rx)
+    };
+
+    let live = BroadcastStream::new(rx).filter_map(|result| result.ok());
+    let comments = tokio_stream::iter(missed)
+        .chain(live)
+        .map(move |(id, comment)| {
+            let row_html = render_comment_row(&comment, preview_chars);
+            Ok(Event::default()
+                .event("comment")
+                .id(id.to_string())
+                .data(row_html))
+        });
+
+    // Header counters; a lagged receiver just skips to the latest totals
+    let error_log = state.error_log.clone();
+    let stats = BroadcastStream::new(state.stats_tx.subscribe()).filter_map(move |result| {
+        let stats = result.ok()?;
+        let panel_html = render_stats_panel(&stats, error_log.total());
+        Some(Ok(Event::default().event("stats").data(panel_html)))
+    });
+
+    // Ends the stream on shutdown; otherwise open tabs keep the server from stopping
+    let shutdown = state.shutdown.clone();
+    let stop = tokio_stream::once(()).then(move |_| {
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown.cancelled().await;
+            None
         }
     });
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+    let events = comments.merge(stats).map(Some).merge(stop).map_while(|e| e);
+    Sse::new(events).keep_alive(KeepAlive::default())
 }
 
-fn render_comment_row(c: &AnalyzedComment) -> String {
+fn render_comment_row(c: &AnalyzedComment, preview_chars: usize) -> String {
     let lead_class = if c.is_lead { "is-lead" } else { "" };
     let lead_badge = if c.is_lead {
         format!(
-            r#"<span class="lead-badge" title="{}">LEAD {:.0}%</span>"#,
+            r#"<a class="lead-badge" href="/lead/{}/{}" title="{}">LEAD {:.0}%</a> <button class="contact-toggle" data-channel="{}" data-comment="{}">Связаться</button>"#,
+            path_segment(&c.channel),
+            c.comment_id,
             html_escape(&c.need_summary),
-            c.lead_score * 100.0
+            c.lead_score * 100.0,
+            html_escape(&c.channel),
+            c.comment_id
         )
     } else {
         String::new()
@@ -46,37 +96,82 @@ fn render_comment_row(c: &AnalyzedComment) -> String {
         String::new()
     };
 
+    let (preview, truncated) = c.text_preview(preview_chars);
+    let text = match c.comment_url().filter(|_| truncated) {
+        Some(url) => format!(
+            r#"{} <a class="show-more" href="{}" target="_blank">ещё</a>"#,
+            html_escape(&preview),
+            html_escape(&url)
+        ),
+        None => html_escape(&preview),
+    };
+
+    let mut author_badges = String::new();
+    if c.is_verified {
+        author_badges.push_str(r#" <span class="author-badge verified" title="Verified">✓</span>"#);
+    }
+    if c.is_premium {
+        author_badges.push_str(r#" <span class="author-badge premium" title="Telegram Premium">★</span>"#);
+    }
+
     let username = c.username.as_deref().map(|u| format!("@{}", html_escape(u))).unwrap_or_default();
-    let phone = c.phone.as_deref().map(|p| html_escape(p)).unwrap_or_default();
+    let phone = c.phone_display().map(html_escape).unwrap_or_default();
+    let phone = if c.phone_unverified() {
+        format!(r#"<td class="phone unverified" title="Не удалось проверить номер">{}</td>"#, phone)
+    } else {
+        format!(r#"<td class="phone">{}</td>"#, phone)
+    };
+    let reactions = if c.reactions.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<div class="reactions">{}</div>"#, html_escape(&c.reactions_display()))
+    };
 
     format!(
         r#"<tr class="comment-row {} {}">
   <td class="lead-cell">{}</td>
   <td class="channel">@{}</td>
-  <td class="author">{}</td>
+  <td class="author">{}{}</td>
   <td class="username">{}</td>
-  <td class="phone">{}</td>
-  <td class="text">{}{}</td>
+  {}
+  <td class="text">{}{}{}</td>
   <td class="intent"><span class="badge {}">{}</span></td>
   <td class="confidence">{:.0}%</td>
+  <td class="sentiment {}">{}</td>
   <td class="date">{}</td>
+  <td class="velocity">{}</td>
 </tr>"#,
         c.intent.css_class(),
         lead_class,
         lead_badge,
         html_escape(&c.channel),
         html_escape(&c.author),
+        author_badges,
         username,
         phone,
-        html_escape(&c.text),
+        text,
+        reactions,
         need,
         c.intent.css_class(),
         c.intent,
         c.confidence * 100.0,
+        sentiment_css(c.sentiment),
+        format_sentiment(c.sentiment),
         c.date.format("%H:%M:%S"),
+        format_velocity(c.comment_velocity),
     )
 }
 
+/// Percent-encodes `s` for use as one URL path segment.
+fn path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
 fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -1,58 +1,323 @@
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
 
-use crate::analysis::{AnalyzedComment, Intent};
+use crate::analysis::{AnalysisPipeline, AnalyzedComment, Intent};
+use crate::config::WebConfig;
+use crate::diagnostics::ErrorLog;
+use crate::storage::{cap_leads, AuthorHistory, ChannelStats, KafkaMetrics, LeadContact, LifetimeStats};
+use crate::telegram::TelegramHealth;
+use super::auth::BasicAuth;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub tx: broadcast::Sender<AnalyzedComment>,
-    pub recent: Arc<RwLock<Vec<AnalyzedComment>>>,
+    /// Comments with their SSE event ID, sent once they are in `recent`
+    pub comment_tx: broadcast::Sender<(u64, AnalyzedComment)>,
+    /// Event ID of the newest comment in `recent`; only changed under its write lock,
+    /// so `recent[i]` has ID `last_event_id - (len - 1 - i)`
+    pub last_event_id: Arc<AtomicU64>,
+    /// Totals after each `push_comment`, for the `stats` SSE event
+    pub stats_tx: broadcast::Sender<Stats>,
+    /// Last `buffer_size` comments, oldest first
+    pub recent: Arc<RwLock<VecDeque<AnalyzedComment>>>,
+    /// At most `max_leads` (0 = no cap)
     pub leads: Arc<RwLock<Vec<AnalyzedComment>>>,
+    pub max_leads: usize,
+    /// Contacted marks, forwarded to the storage writer for leads.json
+    pub lead_contact_tx: mpsc::Sender<LeadContact>,
     pub stats: Arc<RwLock<Stats>>,
+    /// People behind the leads (see `AnalyzedComment::person_key`), for `Stats::unique_leads`
+    pub lead_people: Arc<RwLock<HashSet<String>>>,
+    /// When each unique lead (see `AnalyzedComment::lead_key`) was first seen
+    pub lead_first_seen: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    /// Per-channel counts for /api/channels, kept like the storage writer's
+    pub channel_stats: Arc<RwLock<ChannelStats>>,
     pub buffer_size: usize,
+    /// Per-author comment history, if enabled
+    pub authors: Option<AuthorHistory>,
+    pub analyzer: Arc<AnalysisPipeline>,
+    /// Kafka sink counters (None = no sink running)
+    pub kafka: Option<Arc<KafkaMetrics>>,
+    pub telegram_health: TelegramHealth,
+    pub error_log: ErrorLog,
+    /// Last /api/selftest run; reused until `selftest_interval` elapses
+    pub selftest: Arc<Mutex<Option<(Instant, SelfTestReport)>>>,
+    pub selftest_interval: Duration,
+    /// Max characters of comment text shown (0 = no truncation)
+    pub preview_chars: usize,
+    /// Start times of POST /api/analyze calls within the last minute
+    pub analyze_calls: Arc<Mutex<VecDeque<Instant>>>,
+    pub analyze_rate_per_min: usize,
+    pub title: String,
+    pub logo_url: Option<String>,
+    /// Required credentials (None = no auth)
+    pub auth: Option<Arc<BasicAuth>>,
+    /// Cancelled on shutdown; ends open SSE streams so the server can stop
+    pub shutdown: CancellationToken,
+    pub started_at: Instant,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SelfTestReport {
+    pub ok: bool,
+    pub latency_ms: u128,
+    pub checked_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intent: Option<Intent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_lead: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lead_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Stats {
     pub total: usize,
     pub leads: usize,
+    /// Distinct people among the leads, across channels
+    pub unique_leads: usize,
     pub by_intent: HashMap<Intent, usize>,
 }
 
+impl From<&LifetimeStats> for Stats {
+    fn from(lifetime: &LifetimeStats) -> Self {
+        Self {
+            total: lifetime.total,
+            leads: lifetime.leads,
+            unique_leads: 0,
+            by_intent: lifetime.by_intent.clone(),
+        }
+    }
+}
+
 impl AppState {
-    pub fn new(tx: broadcast::Sender<AnalyzedComment>, buffer_size: usize) -> Self {
+    pub fn new(
+        config: &WebConfig,
+        authors: Option<AuthorHistory>,
+        analyzer: Arc<AnalysisPipeline>,
+        telegram_health: TelegramHealth,
+        error_log: ErrorLog,
+        lead_contact_tx: mpsc::Sender<LeadContact>,
+        shutdown: CancellationToken,
+    ) -> Self {
+        let buffer_size = config.recent_buffer_size;
+        let (stats_tx, _) = broadcast::channel(16);
+        let (comment_tx, _) = broadcast::channel(256);
+        // Start from the clock so IDs keep increasing across restarts and a
+        // reconnecting tab never claims to be ahead of us
+        let first_event_id = Utc::now().timestamp_millis().max(0) as u64;
         Self {
-            tx,
-            recent: Arc::new(RwLock::new(Vec::with_capacity(buffer_size))),
+            comment_tx,
+            last_event_id: Arc::new(AtomicU64::new(first_event_id)),
+            stats_tx,
+            recent: Arc::new(RwLock::new(VecDeque::with_capacity(buffer_size))),
             leads: Arc::new(RwLock::new(Vec::new())),
+            max_leads: 0,
+            lead_contact_tx,
             stats: Arc::new(RwLock::new(Stats::default())),
+            lead_people: Arc::new(RwLock::new(HashSet::new())),
+            lead_first_seen: Arc::new(RwLock::new(HashMap::new())),
+            channel_stats: Arc::new(RwLock::new(ChannelStats::default())),
             buffer_size,
+            authors,
+            analyzer,
+            kafka: None,
+            telegram_health,
+            error_log,
+            selftest: Arc::new(Mutex::new(None)),
+            selftest_interval: Duration::from_secs(config.selftest_interval_secs),
+            preview_chars: config.preview_chars,
+            analyze_calls: Arc::new(Mutex::new(VecDeque::new())),
+            analyze_rate_per_min: config.analyze_rate_per_min,
+            title: config.title.clone(),
+            logo_url: config.logo_url.clone(),
+            auth: config.auth.as_ref().map(|a| Arc::new(BasicAuth::new(a))),
+            shutdown,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Caps the leads kept on the dashboard, like `storage.max_leads` does for leads.json.
+    pub fn with_max_leads(mut self, max_leads: usize) -> Self {
+        self.max_leads = max_leads;
+        self
+    }
+
+    /// Serves the Kafka sink's counters at /metrics/kafka.
+    pub fn with_kafka(mut self, metrics: Option<Arc<KafkaMetrics>>) -> Self {
+        self.kafka = metrics;
+        self
+    }
+
+    /// Replaces the leads with those restored from storage and counts their people.
+    /// Each lead counts as first seen when it was analyzed, so /api/leads/new
+    /// keeps answering across restarts.
+    pub async fn restore_leads(&self, mut leads: Vec<AnalyzedComment>) {
+        cap_leads(&mut leads, self.max_leads);
+        let people: HashSet<String> = leads.iter().map(|c| c.person_key()).collect();
+        let mut first_seen: HashMap<String, DateTime<Utc>> = HashMap::new();
+        for c in &leads {
+            let seen_at = first_seen.entry(c.lead_key()).or_insert(c.analyzed_at);
+            *seen_at = (*seen_at).min(c.analyzed_at);
+        }
+        self.stats.write().await.unique_leads = people.len();
+        *self.lead_people.write().await = people;
+        *self.lead_first_seen.write().await = first_seen;
+        *self.leads.write().await = leads;
+    }
+
+    /// Marks the lead `comment_id` in `channel` as contacted (or not), keeping the
+    /// original time when it already was. Returns the lead, or None if unknown.
+    pub async fn set_contacted(&self, channel: &str, comment_id: i32, contacted: bool) -> Option<AnalyzedComment> {
+        let channel = channel.trim_start_matches('@');
+        let now = Utc::now();
+        let mut leads = self.leads.write().await;
+        let mut found = None;
+        for lead in leads.iter_mut() {
+            if lead.comment_id != comment_id || !lead.channel.trim_start_matches('@').eq_ignore_ascii_case(channel) {
+                continue;
+            }
+            if lead.contacted != contacted {
+                lead.contacted = contacted;
+                lead.contacted_at = contacted.then_some(now);
+            }
+            found = Some(lead.clone());
+        }
+        drop(leads);
+
+        let lead = found?;
+        let contact = LeadContact {
+            channel: lead.channel.clone(),
+            comment_id,
+            contacted_at: lead.contacted_at,
+        };
+        if self.lead_contact_tx.send(contact).await.is_err() {
+            tracing::warn!("Storage writer stopped, contacted mark not saved to leads.json");
         }
+        Some(lead)
     }
 
     pub async fn push_comment(&self, comment: AnalyzedComment) {
+        if let Some(authors) = &self.authors {
+            if let Err(e) = authors.record(&comment).await {
+                tracing::error!("Failed to record author history: {:#}", e);
+            }
+        }
+
+        if comment.edited {
+            self.replace_edited(comment).await;
+            return;
+        }
+
+        self.channel_stats.write().await.record(&comment);
+
         {
             let mut stats = self.stats.write().await;
             stats.total += 1;
             if comment.is_lead {
                 stats.leads += 1;
+                let mut people = self.lead_people.write().await;
+                people.insert(comment.person_key());
+                stats.unique_leads = people.len();
             }
-            *stats.by_intent.entry(comment.intent).or_insert(0) += 1;
+            *stats.by_intent.entry(comment.intent.clone()).or_insert(0) += 1;
+            // No receivers just means no dashboard is open
+            let _ = self.stats_tx.send(stats.clone());
         }
 
         if comment.is_lead {
+            // Locked before `leads`, in the same order as /api/leads/new
+            let mut first_seen = self.lead_first_seen.write().await;
+            first_seen.entry(comment.lead_key()).or_insert(comment.analyzed_at);
+
             let mut leads = self.leads.write().await;
             leads.push(comment.clone());
             leads.sort_by(|a, b| b.lead_score.partial_cmp(&a.lead_score).unwrap_or(std::cmp::Ordering::Equal));
+            cap_leads(&mut leads, self.max_leads);
+            prune_first_seen(&mut first_seen, &leads);
         }
 
         {
             let mut recent = self.recent.write().await;
             if recent.len() >= self.buffer_size {
-                recent.remove(0);
+                recent.pop_front();
             }
-            recent.push(comment);
+            recent.push_back(comment.clone());
+            let id = self.last_event_id.fetch_add(1, Ordering::SeqCst) + 1;
+            // Sent under the lock so SSE replay (which subscribes under the read
+            // lock) sees each comment exactly once
+            let _ = self.comment_tx.send((id, comment));
         }
     }
+
+    /// Records whether `channel` has comments enabled, as reported by the scraper.
+    pub async fn set_channel_status(&self, channel: String, has_comments: bool) {
+        self.channel_stats.write().await.set_has_comments(channel, has_comments);
+    }
+
+    /// Swaps an edited comment's re-analysis in for the original in the recent
+    /// buffer and the leads, keeping its contacted mark. It isn't counted again;
+    /// only a comment that became a lead through the edit adds to the lead stats.
+    async fn replace_edited(&self, mut comment: AnalyzedComment) {
+        {
+            let mut channel_stats = self.channel_stats.write().await;
+            let leads = self.leads.read().await;
+            let recent = self.recent.read().await;
+            let counted = leads
+                .iter()
+                .chain(recent.iter())
+                .find(|c| c.same_comment(&comment))
+                .is_some_and(|previous| channel_stats.counts_as_lead(previous));
+            if !counted && channel_stats.counts_as_lead(&comment) {
+                channel_stats.record_new_lead(&comment.channel);
+            }
+        }
+
+        let was_lead = {
+            let mut first_seen = self.lead_first_seen.write().await;
+            let mut leads = self.leads.write().await;
+            let previous = leads.iter().position(|c| c.same_comment(&comment)).map(|i| leads.remove(i));
+            if let Some(previous) = &previous {
+                comment.contacted = previous.contacted;
+                comment.contacted_at = previous.contacted_at;
+            }
+            if comment.is_lead {
+                if previous.is_none() {
+                    first_seen.entry(comment.lead_key()).or_insert(comment.analyzed_at);
+                }
+                leads.push(comment.clone());
+                leads.sort_by(|a, b| b.lead_score.partial_cmp(&a.lead_score).unwrap_or(std::cmp::Ordering::Equal));
+                cap_leads(&mut leads, self.max_leads);
+            }
+            prune_first_seen(&mut first_seen, &leads);
+            previous.is_some()
+        };
+
+        if comment.is_lead && !was_lead {
+            let mut stats = self.stats.write().await;
+            stats.leads += 1;
+            let mut people = self.lead_people.write().await;
+            people.insert(comment.person_key());
+            stats.unique_leads = people.len();
+            let _ = self.stats_tx.send(stats.clone());
+        }
+
+        let mut recent = self.recent.write().await;
+        if let Some(slot) = recent.iter_mut().find(|c| c.same_comment(&comment)) {
+            *slot = comment;
+        }
+    }
+}
+
+/// Forgets the first-seen times of leads no longer kept (dropped by `cap_leads`
+/// or un-leaded by an edit), so the map stays bounded by `max_leads`.
+fn prune_first_seen(first_seen: &mut HashMap<String, DateTime<Utc>>, leads: &[AnalyzedComment]) {
+    let kept: HashSet<String> = leads.iter().map(|c| c.lead_key()).collect();
+    first_seen.retain(|key, _| kept.contains(key));
 }